@@ -0,0 +1,60 @@
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use nothing_moves::maze::{Coord, GeneratorVersion, Maze};
+
+const LENGTHS: [[Coord; 3]; 2] = [[16, 16, 16], [32, 32, 32]];
+
+fn generation(c: &mut Criterion) {
+    let mut group = c.benchmark_group("generation");
+    group.sample_size(10);
+    for lengths in LENGTHS {
+        let cells = lengths.iter().map(|l| *l as u64).product();
+        group.throughput(Throughput::Elements(cells));
+        group.bench_with_input(
+            BenchmarkId::from_parameter(cells),
+            &lengths,
+            |b, lengths| {
+                b.iter(|| {
+                    let mut rng = GeneratorVersion::LATEST.rng(684153987);
+                    Maze::new(lengths, &mut rng)
+                })
+            },
+        );
+    }
+    group.finish();
+}
+
+fn can_move(c: &mut Criterion) {
+    let mut group = c.benchmark_group("can_move");
+    for lengths in LENGTHS {
+        let mut rng = GeneratorVersion::LATEST.rng(684153987);
+        let maze = Maze::new(&lengths, &mut rng);
+        let cells: Vec<_> = (0..lengths[0])
+            .flat_map(|x| {
+                (0..lengths[1]).flat_map(move |y| (0..lengths[2]).map(move |z| [x, y, z]))
+            })
+            .collect();
+
+        group.throughput(Throughput::Elements(cells.len() as u64 * 3));
+        group.bench_with_input(
+            BenchmarkId::from_parameter(cells.len()),
+            &cells,
+            |b, cells| {
+                b.iter(|| {
+                    let mut open = 0;
+                    for cell in cells {
+                        for dim in 0..3 {
+                            if let Some(true) = maze.can_move(black_box(cell), dim) {
+                                open += 1;
+                            }
+                        }
+                    }
+                    open
+                })
+            },
+        );
+    }
+    group.finish();
+}
+
+criterion_group!(benches, generation, can_move);
+criterion_main!(benches);
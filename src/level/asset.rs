@@ -0,0 +1,480 @@
+use crate::maze::{
+    self, Coord, FixedPassages, FormatError, Generator, GeneratorVersion, Grid, PassageRecord,
+};
+use bevy::{
+    asset::{AssetLoader, AssetPath, BoxedFuture, LoadContext, LoadedAsset},
+    ecs::system::SystemParam,
+    prelude::*,
+    reflect::TypeUuid,
+};
+use serde::{Deserialize, Serialize};
+use std::{fs, io, marker::PhantomData, path::Path};
+
+use super::{
+    loader::{DimensionLength, LayoutSource, LevelInfo, LoadLevel, RngSource},
+    maze_level::{GoalPlacement, LevelCompleted},
+};
+
+/// The longest a level's dimension can be, enough for endurance corridors while keeping the
+/// walls of a slice through the maze few enough to spawn.
+pub const MAX_LENGTH: Coord = 4096;
+
+/// A level authored as a `.maze.ron` file.
+#[derive(Clone, Debug, Deserialize, TypeUuid)]
+#[uuid = "3b0f5a0e-8d0c-4a4f-9c59-7f0e62d3c1a4"]
+pub struct MazeLevelAsset {
+    pub title: String,
+    pub lengths: Vec<Coord>,
+    /// Dimensions that wrap around from their last cell to their first, indexed by dimension.
+    #[serde(default)]
+    pub wraps: Vec<bool>,
+    /// The cell the player starts in, the origin when empty.
+    #[serde(default)]
+    pub start: Vec<Coord>,
+    #[serde(default)]
+    pub goal: GoalPlacement,
+    pub layout: LevelLayout,
+}
+
+/// How the passages of a level are produced.
+#[derive(Clone, Debug, Deserialize)]
+pub enum LevelLayout {
+    /// Generated from a seed, always producing the same maze for the same version.
+    Generated {
+        seed: u64,
+        /// The generator version the seed was picked with, the first version when left out.
+        #[serde(default)]
+        version: GeneratorVersion,
+        #[serde(default)]
+        generator: Generator,
+        /// The fraction of dead ends to open into loops after generation.
+        #[serde(default)]
+        braid: f32,
+    },
+    /// Every open passage, listed explicitly.
+    Passages(Vec<PassageRecord>),
+}
+
+impl MazeLevelAsset {
+    /// Checks the level can be built, so broken files fail when loaded rather than when played.
+    pub fn validate(&self) -> Result<(), FormatError> {
+        if self.lengths.len() < 2 {
+            return Err(FormatError::Invalid(
+                "a level needs at least two dimensions",
+            ));
+        }
+        if self.lengths.contains(&0) {
+            return Err(FormatError::Invalid(
+                "every dimension needs at least one cell",
+            ));
+        }
+
+        if self.lengths.iter().any(|length| *length > MAX_LENGTH) {
+            return Err(FormatError::Invalid(
+                "a dimension is longer than a level allows",
+            ));
+        }
+        if Grid::checked_cell_count(&self.lengths).is_none() {
+            return Err(FormatError::Invalid("the maze has too many cells"));
+        }
+
+        let grid = Grid::wrapping(&self.lengths, &self.wraps);
+        let in_bounds = |cell: &[Coord]| cell.len() == grid.dims() && grid.index_of(cell).is_some();
+        if !self.start.is_empty() && !in_bounds(&self.start) {
+            return Err(FormatError::Invalid("start is outside of the maze"));
+        }
+        if let GoalPlacement::At(goal) = &self.goal {
+            if !in_bounds(goal) {
+                return Err(FormatError::Invalid("goal is outside of the maze"));
+            }
+        }
+        if let LevelLayout::Passages(passages) = &self.layout {
+            maze::validate_passages(&grid, passages)?;
+        }
+        Ok(())
+    }
+
+    pub fn load_level(&self) -> LoadLevel {
+        let layout = match &self.layout {
+            LevelLayout::Generated {
+                seed,
+                version,
+                generator,
+                braid,
+            } => LayoutSource::Generated {
+                rng_source: RngSource::Seeded(*seed),
+                version: *version,
+                generator: generator.clone(),
+                braid: *braid,
+            },
+            LevelLayout::Passages(passages) => {
+                LayoutSource::Stored(FixedPassages(passages.clone()))
+            }
+        };
+        LoadLevel {
+            layout,
+            dimensions: DimensionLength::from_lengths(&self.lengths),
+            start: self.start.clone(),
+            wraps: self.wraps.clone(),
+            goal: self.goal.clone(),
+            title: self.title.clone(),
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct MazeLevelAssetLoader;
+
+impl AssetLoader for MazeLevelAssetLoader {
+    fn load<'a>(
+        &'a self,
+        bytes: &'a [u8],
+        load_context: &'a mut LoadContext,
+    ) -> BoxedFuture<'a, Result<(), anyhow::Error>> {
+        Box::pin(async move {
+            let level: MazeLevelAsset = ron::de::from_bytes(bytes).map_err(FormatError::from)?;
+            level.validate()?;
+            load_context.set_default_asset(LoadedAsset::new(level));
+            Ok(())
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["maze.ron"]
+    }
+}
+
+/// The `.pack.ron` manifest, listing level files relative to the manifest.
+#[derive(Deserialize)]
+struct LevelPackManifest {
+    levels: Vec<String>,
+}
+
+/// An ordered set of levels, played from first to last.
+#[derive(Debug, TypeUuid)]
+#[uuid = "c6a1d7f2-52e4-4e0b-b1b8-0f4d9a6e2b57"]
+pub struct LevelPack {
+    pub levels: Vec<Handle<MazeLevelAsset>>,
+}
+
+#[derive(Default)]
+pub struct LevelPackLoader;
+
+impl AssetLoader for LevelPackLoader {
+    fn load<'a>(
+        &'a self,
+        bytes: &'a [u8],
+        load_context: &'a mut LoadContext,
+    ) -> BoxedFuture<'a, Result<(), anyhow::Error>> {
+        Box::pin(async move {
+            let manifest: LevelPackManifest =
+                ron::de::from_bytes(bytes).map_err(FormatError::from)?;
+            let folder = load_context
+                .path()
+                .parent()
+                .map(|p| p.to_path_buf())
+                .unwrap_or_default();
+            let paths: Vec<AssetPath> = manifest
+                .levels
+                .iter()
+                .map(|level| AssetPath::new(folder.join(level), None))
+                .collect();
+
+            let pack = LevelPack {
+                levels: paths
+                    .iter()
+                    .map(|path| load_context.get_handle(path.clone()))
+                    .collect(),
+            };
+            load_context.set_default_asset(LoadedAsset::new(pack).with_dependencies(paths));
+            Ok(())
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["pack.ron"]
+    }
+}
+
+/// The loaded packs and the levels in them, read together to find the levels of a pack.
+#[derive(SystemParam)]
+pub struct LevelAssets<'w, 's> {
+    pub packs: Res<'w, Assets<LevelPack>>,
+    pub levels: Res<'w, Assets<MazeLevelAsset>>,
+    #[system_param(ignore)]
+    _marker: PhantomData<&'s ()>,
+}
+
+/// Where pack progress is read from on startup, and written to whenever a pack level is completed.
+pub const PROGRESS_PATH: &str = "config/progress.ron";
+
+/// The part of [`LevelPackProgress`] kept between runs.
+#[derive(Serialize, Deserialize)]
+struct SavedProgress {
+    level: usize,
+    #[serde(default)]
+    last_seed: Option<u64>,
+    /// The version `last_seed` was generated with, saves from before versioning only hold V1 seeds.
+    #[serde(default)]
+    last_version: GeneratorVersion,
+}
+
+/// The pack being played through, and how far into it the player has got.
+pub struct LevelPackProgress {
+    pub pack: Handle<LevelPack>,
+    /// The furthest level unlocked, one past the last level once the pack is finished.
+    pub level: usize,
+    /// The seed of the last maze played, from the pack or not, with the version it was generated by.
+    pub last_seed: Option<(u64, GeneratorVersion)>,
+    /// The level of the pack being played, if the current maze came from the pack.
+    playing: Option<usize>,
+}
+
+impl LevelPackProgress {
+    pub fn new(pack: Handle<LevelPack>) -> Self {
+        Self {
+            pack,
+            level: 0,
+            last_seed: None,
+            playing: None,
+        }
+    }
+
+    /// Reads the progress for `pack` saved at `path`, starting from the first level if there is none.
+    pub fn restore(pack: Handle<LevelPack>, path: impl AsRef<Path>) -> Self {
+        let mut progress = Self::new(pack);
+        match fs::read_to_string(path) {
+            Ok(text) => match ron::from_str::<SavedProgress>(&text) {
+                Ok(saved) => {
+                    progress.level = saved.level;
+                    progress.last_seed = saved.last_seed.map(|seed| (seed, saved.last_version));
+                }
+                Err(e) => warn!("Couldn't read level progress, starting over: {}", e),
+            },
+            Err(e) if e.kind() != io::ErrorKind::NotFound => {
+                warn!("Couldn't read level progress, starting over: {}", e)
+            }
+            Err(_) => {}
+        }
+        progress
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        let path = path.as_ref();
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let saved = SavedProgress {
+            level: self.level,
+            last_seed: self.last_seed.map(|(seed, _)| seed),
+            last_version: self
+                .last_seed
+                .map_or(GeneratorVersion::LATEST, |(_, version)| version),
+        };
+        let text = ron::ser::to_string_pretty(&saved, Default::default())
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        fs::write(path, text)
+    }
+
+    /// The number of levels in the pack, zero until it has loaded.
+    pub fn level_count(&self, packs: &Assets<LevelPack>) -> usize {
+        packs.get(&self.pack).map_or(0, |pack| pack.levels.len())
+    }
+
+    /// The level at `index` in the pack, if both the pack and the level have loaded.
+    pub fn level_at<'a>(
+        &self,
+        index: usize,
+        packs: &Assets<LevelPack>,
+        levels: &'a Assets<MazeLevelAsset>,
+    ) -> Option<&'a MazeLevelAsset> {
+        packs
+            .get(&self.pack)
+            .and_then(|pack| pack.levels.get(index))
+            .and_then(|level| levels.get(level))
+    }
+
+    /// Whether the level at `index` has been reached yet.
+    pub fn is_unlocked(&self, index: usize) -> bool {
+        index <= self.level
+    }
+
+    /// Builds the level at `index` to be played, noting it so completing it advances the pack.
+    pub fn start(
+        &mut self,
+        index: usize,
+        packs: &Assets<LevelPack>,
+        levels: &Assets<MazeLevelAsset>,
+    ) -> Option<LoadLevel> {
+        let level = self.level_at(index, packs, levels)?.load_level();
+        self.playing = Some(index);
+        Some(level)
+    }
+
+    /// Builds the level after the one being played, if the current maze came from the pack.
+    pub fn start_next(
+        &mut self,
+        packs: &Assets<LevelPack>,
+        levels: &Assets<MazeLevelAsset>,
+    ) -> Option<LoadLevel> {
+        let next = self.playing? + 1;
+        self.start(next, packs, levels)
+    }
+
+    /// Notes that the next maze doesn't come from the pack.
+    pub fn leave(&mut self) {
+        self.playing = None;
+    }
+
+    /// Unlocks the level after the one being played, returning whether that changed anything.
+    fn complete(&mut self) -> bool {
+        match self.playing {
+            Some(index) if index >= self.level => {
+                self.level = index + 1;
+                true
+            }
+            _ => false,
+        }
+    }
+}
+
+/// Saves the seed of each generated level as it starts, so it can be played again later.
+pub fn record_level_seed(info: Res<LevelInfo>, progress: Option<ResMut<LevelPackProgress>>) {
+    if let (Some(seed), Some(mut progress)) = (info.seed, progress) {
+        progress.last_seed = Some(seed);
+        if let Err(e) = progress.save(PROGRESS_PATH) {
+            warn!("Couldn't save level progress: {}", e);
+        }
+    }
+}
+
+/// Unlocks the next level of the pack once the current one is solved.
+pub fn advance_pack_progress(
+    mut completed: EventReader<LevelCompleted>,
+    progress: Option<ResMut<LevelPackProgress>>,
+) {
+    if let Some(mut progress) = progress {
+        if completed.iter().count() > 0 && progress.complete() {
+            if let Err(e) = progress.save(PROGRESS_PATH) {
+                warn!("Couldn't save level progress: {}", e);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(source: &str) -> MazeLevelAsset {
+        let level: MazeLevelAsset = ron::from_str(source).unwrap();
+        level.validate().unwrap();
+        level
+    }
+
+    #[test]
+    fn shipped_levels_are_valid() {
+        let folder = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("assets/levels");
+        let read = |file: &str| std::fs::read_to_string(folder.join(file)).unwrap();
+
+        let manifest: LevelPackManifest = ron::from_str(&read("main.pack.ron")).unwrap();
+        assert!(!manifest.levels.is_empty());
+        for level in manifest.levels {
+            parse(&read(&level));
+        }
+    }
+
+    #[test]
+    fn unversioned_levels_use_the_first_version() {
+        let level = parse("(title: \"Old\", lengths: [3, 3], layout: Generated(seed: 7))");
+        assert!(matches!(
+            level.load_level().layout,
+            LayoutSource::Generated {
+                version: GeneratorVersion::V1,
+                ..
+            }
+        ));
+
+        let level =
+            parse("(title: \"New\", lengths: [3, 3], layout: Generated(seed: 7, version: V1))");
+        assert!(matches!(
+            level.load_level().layout,
+            LayoutSource::Generated {
+                version: GeneratorVersion::V1,
+                ..
+            }
+        ));
+    }
+
+    #[test]
+    fn rejects_oversized_levels() {
+        let level: MazeLevelAsset = ron::from_str(&format!(
+            "(title: \"Huge\", lengths: {:?}, layout: Generated(seed: 1))",
+            [Coord::MAX; 8]
+        ))
+        .unwrap();
+        assert!(level.validate().is_err());
+    }
+
+    #[test]
+    fn caps_level_lengths() {
+        let parse_lengths = |lengths: &[Coord]| -> MazeLevelAsset {
+            ron::from_str(&format!(
+                "(title: \"Long\", lengths: {:?}, layout: Generated(seed: 1))",
+                lengths
+            ))
+            .unwrap()
+        };
+        assert!(parse_lengths(&[1000, 3, 3]).validate().is_ok());
+        assert!(parse_lengths(&[MAX_LENGTH, 2]).validate().is_ok());
+        assert!(parse_lengths(&[MAX_LENGTH + 1, 2]).validate().is_err());
+        assert!(parse_lengths(&[Coord::MAX, 2]).validate().is_err());
+    }
+
+    #[test]
+    fn rejects_passages_outside_of_maze() {
+        let level: MazeLevelAsset = ron::from_str(
+            "(title: \"Broken\", lengths: [2, 2], layout: Passages([(cell: [1, 0], dim: 0)]))",
+        )
+        .unwrap();
+        assert!(level.validate().is_err());
+    }
+
+    #[test]
+    fn completing_levels_unlocks_the_next() {
+        let mut progress = LevelPackProgress::new(Handle::default());
+        assert!(!progress.complete());
+
+        progress.playing = Some(0);
+        assert!(progress.complete());
+        assert!(progress.is_unlocked(1));
+        assert!(!progress.is_unlocked(2));
+
+        // Replaying an earlier level doesn't move progress back.
+        progress.playing = Some(0);
+        assert!(!progress.complete());
+        assert_eq!(progress.level, 1);
+
+        progress.leave();
+        assert!(!progress.complete());
+    }
+
+    #[test]
+    fn progress_round_trips_through_file() {
+        let path = std::env::temp_dir().join("nothing-moves-progress-test.ron");
+        let mut progress = LevelPackProgress::new(Handle::default());
+        progress.level = 2;
+        progress.last_seed = Some((42, GeneratorVersion::V1));
+        progress.save(&path).unwrap();
+
+        let restored = LevelPackProgress::restore(Handle::default(), &path);
+        assert_eq!(restored.level, 2);
+        assert_eq!(restored.last_seed, Some((42, GeneratorVersion::V1)));
+
+        // Saves from before versioning kept only the seed.
+        fs::write(&path, "(level: 1, last_seed: Some(7))").unwrap();
+        let restored = LevelPackProgress::restore(Handle::default(), &path);
+        assert_eq!(restored.last_seed, Some((7, GeneratorVersion::V1)));
+        fs::remove_file(path).unwrap();
+    }
+}
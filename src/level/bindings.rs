@@ -0,0 +1,365 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    fs, io,
+    path::Path,
+};
+
+use bevy::{ecs::event::Events, input::Axis as InputAxis, prelude::*};
+use serde::{Deserialize, Serialize};
+
+use super::maze_level::{Axis, Direction};
+
+/// Where bindings are read from on startup, and written to whenever they change.
+pub const BINDINGS_PATH: &str = "config/bindings.ron";
+
+/// Something the player can do, independent of the key it's bound to.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum Action {
+    Move(Axis, Direction),
+    ShiftAxis(Axis, Direction),
+    /// Picks the highlighted menu entry.
+    Confirm,
+    /// Leaves the current menu.
+    Back,
+}
+
+impl Action {
+    pub const ALL: [Action; 10] = [
+        Action::Move(Axis::X, Direction::Positive),
+        Action::Move(Axis::X, Direction::Negative),
+        Action::Move(Axis::Y, Direction::Positive),
+        Action::Move(Axis::Y, Direction::Negative),
+        Action::ShiftAxis(Axis::X, Direction::Negative),
+        Action::ShiftAxis(Axis::X, Direction::Positive),
+        Action::ShiftAxis(Axis::Y, Direction::Negative),
+        Action::ShiftAxis(Axis::Y, Direction::Positive),
+        Action::Confirm,
+        Action::Back,
+    ];
+}
+
+/// Sent for each action the player triggered this frame, from any device.
+#[derive(Clone, Debug)]
+pub struct ActionPressed(pub Action);
+
+/// The kind of device the player last used, which the control hints are shown for.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum InputDevice {
+    #[default]
+    Keyboard,
+    Gamepad,
+}
+
+/// How far a stick has to be pushed before it counts as a move.
+const STICK_THRESHOLD: f32 = 0.5;
+
+/// The key and gamepad button bound to each action.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ActionBindings {
+    keys: BTreeMap<Action, KeyCode>,
+    #[serde(default)]
+    buttons: BTreeMap<Action, GamepadButtonType>,
+}
+
+impl Default for ActionBindings {
+    fn default() -> Self {
+        let keys = [
+            KeyCode::W,
+            KeyCode::S,
+            KeyCode::D,
+            KeyCode::A,
+            KeyCode::Q,
+            KeyCode::E,
+            KeyCode::Z,
+            KeyCode::X,
+            KeyCode::Return,
+            KeyCode::Escape,
+        ];
+        let buttons = [
+            GamepadButtonType::DPadUp,
+            GamepadButtonType::DPadDown,
+            GamepadButtonType::DPadRight,
+            GamepadButtonType::DPadLeft,
+            GamepadButtonType::LeftTrigger,
+            GamepadButtonType::RightTrigger,
+            GamepadButtonType::LeftTrigger2,
+            GamepadButtonType::RightTrigger2,
+            GamepadButtonType::South,
+            GamepadButtonType::East,
+        ];
+        Self {
+            keys: Action::ALL.into_iter().zip(keys).collect(),
+            buttons: Action::ALL.into_iter().zip(buttons).collect(),
+        }
+    }
+}
+
+impl ActionBindings {
+    pub fn key_for(&self, action: Action) -> Option<KeyCode> {
+        self.keys.get(&action).copied()
+    }
+
+    /// Binds `key` to `action`, unbinding it from whatever action it was previously bound to.
+    pub fn bind(&mut self, action: Action, key: KeyCode) {
+        self.keys.retain(|_, bound| *bound != key);
+        self.keys.insert(action, key);
+    }
+
+    pub fn button_for(&self, action: Action) -> Option<GamepadButtonType> {
+        self.buttons.get(&action).copied()
+    }
+
+    /// Binds `button` to `action`, unbinding it from whatever action it was previously bound to.
+    pub fn bind_button(&mut self, action: Action, button: GamepadButtonType) {
+        self.buttons.retain(|_, bound| *bound != button);
+        self.buttons.insert(action, button);
+    }
+
+    /// The text shown for the input bound to `action` on `device`, empty if it is unbound.
+    pub fn label(&self, action: Action, device: InputDevice) -> String {
+        match device {
+            InputDevice::Keyboard => self.key_for(action).map(key_label),
+            InputDevice::Gamepad => self.button_for(action).map(button_label),
+        }
+        .unwrap_or_default()
+    }
+
+    /// Reads bindings from `path`, falling back to the defaults for any action it doesn't bind.
+    pub fn load(path: impl AsRef<Path>) -> io::Result<Self> {
+        let text = fs::read_to_string(path)?;
+        let mut bindings = Self::default();
+        let loaded: Self =
+            ron::from_str(&text).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        for (action, key) in loaded.keys {
+            bindings.bind(action, key);
+        }
+        for (action, button) in loaded.buttons {
+            bindings.bind_button(action, button);
+        }
+        Ok(bindings)
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        let path = path.as_ref();
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let text = ron::ser::to_string_pretty(self, Default::default())
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        fs::write(path, text)
+    }
+}
+
+fn key_label(key: KeyCode) -> String {
+    match key {
+        KeyCode::Key0 => "0".into(),
+        KeyCode::Key1 => "1".into(),
+        KeyCode::Key2 => "2".into(),
+        KeyCode::Key3 => "3".into(),
+        KeyCode::Key4 => "4".into(),
+        KeyCode::Key5 => "5".into(),
+        KeyCode::Key6 => "6".into(),
+        KeyCode::Key7 => "7".into(),
+        KeyCode::Key8 => "8".into(),
+        KeyCode::Key9 => "9".into(),
+        KeyCode::Up => "^".into(),
+        KeyCode::Down => "v".into(),
+        KeyCode::Left => "<".into(),
+        KeyCode::Right => ">".into(),
+        key => format!("{:?}", key),
+    }
+}
+
+fn button_label(button: GamepadButtonType) -> String {
+    match button {
+        GamepadButtonType::South => "A",
+        GamepadButtonType::East => "B",
+        GamepadButtonType::North => "Y",
+        GamepadButtonType::West => "X",
+        GamepadButtonType::C => "C",
+        GamepadButtonType::Z => "Z",
+        GamepadButtonType::LeftTrigger => "LB",
+        GamepadButtonType::LeftTrigger2 => "LT",
+        GamepadButtonType::RightTrigger => "RB",
+        GamepadButtonType::RightTrigger2 => "RT",
+        GamepadButtonType::Select => "Select",
+        GamepadButtonType::Start => "Start",
+        GamepadButtonType::Mode => "Home",
+        GamepadButtonType::LeftThumb => "LS",
+        GamepadButtonType::RightThumb => "RS",
+        GamepadButtonType::DPadUp => "^",
+        GamepadButtonType::DPadDown => "v",
+        GamepadButtonType::DPadLeft => "<",
+        GamepadButtonType::DPadRight => ">",
+    }
+    .into()
+}
+
+/// The movement a stick is pushed toward, matching the d-pad layout.
+fn stick_action(x: f32, y: f32) -> Option<Action> {
+    if x.abs().max(y.abs()) < STICK_THRESHOLD {
+        None
+    } else if y.abs() >= x.abs() {
+        Some(if y > 0.0 {
+            Action::Move(Axis::X, Direction::Positive)
+        } else {
+            Action::Move(Axis::X, Direction::Negative)
+        })
+    } else {
+        Some(if x > 0.0 {
+            Action::Move(Axis::Y, Direction::Positive)
+        } else {
+            Action::Move(Axis::Y, Direction::Negative)
+        })
+    }
+}
+
+/// Turns key presses and gamepad buttons into actions.
+pub fn read_actions(
+    keys: Res<Input<KeyCode>>,
+    buttons: Res<Input<GamepadButton>>,
+    gamepads: Res<Gamepads>,
+    bindings: Res<ActionBindings>,
+    mut device: ResMut<InputDevice>,
+    mut pressed: EventWriter<ActionPressed>,
+) {
+    for action in Action::ALL {
+        if bindings
+            .key_for(action)
+            .is_some_and(|key| keys.just_pressed(key))
+        {
+            set_device(&mut device, InputDevice::Keyboard);
+            pressed.send(ActionPressed(action));
+        }
+        if let Some(button) = bindings.button_for(action) {
+            if gamepads
+                .iter()
+                .any(|pad| buttons.just_pressed(GamepadButton(*pad, button)))
+            {
+                set_device(&mut device, InputDevice::Gamepad);
+                pressed.send(ActionPressed(action));
+            }
+        }
+    }
+}
+
+/// Turns left stick pushes into moves, pressing once each time the stick is pushed past the threshold.
+pub fn read_stick_actions(
+    sticks: Res<InputAxis<GamepadAxis>>,
+    gamepads: Res<Gamepads>,
+    mut device: ResMut<InputDevice>,
+    mut stick_actions: Local<HashMap<Gamepad, Action>>,
+    mut pressed: EventWriter<ActionPressed>,
+) {
+    for pad in gamepads.iter() {
+        let axis = |axis| sticks.get(GamepadAxis(*pad, axis)).unwrap_or(0.0);
+        let action = stick_action(
+            axis(GamepadAxisType::LeftStickX),
+            axis(GamepadAxisType::LeftStickY),
+        );
+        if action.as_ref() == stick_actions.get(pad) {
+            continue;
+        }
+        match action {
+            Some(action) => {
+                stick_actions.insert(*pad, action);
+                set_device(&mut device, InputDevice::Gamepad);
+                pressed.send(ActionPressed(action));
+            }
+            None => {
+                stick_actions.remove(pad);
+            }
+        }
+    }
+}
+
+/// Only writes the device when it changes, so the hint labels aren't rebuilt every press.
+fn set_device(device: &mut ResMut<InputDevice>, used: InputDevice) {
+    if **device != used {
+        **device = used;
+    }
+}
+
+/// Drops actions pressed before a state change, so the press that caused it isn't handled again.
+pub fn discard_actions(mut actions: ResMut<Events<ActionPressed>>) {
+    actions.clear();
+}
+
+pub fn load_bindings(mut c: Commands) {
+    let bindings = match ActionBindings::load(BINDINGS_PATH) {
+        Ok(bindings) => bindings,
+        Err(e) => {
+            if e.kind() != io::ErrorKind::NotFound {
+                warn!("Couldn't read key bindings, using the defaults: {}", e);
+            }
+            ActionBindings::default()
+        }
+    };
+    c.insert_resource(bindings);
+}
+
+pub fn save_changed_bindings(bindings: Res<ActionBindings>) {
+    if bindings.is_changed() && !bindings.is_added() {
+        if let Err(e) = bindings.save(BINDINGS_PATH) {
+            warn!("Couldn't save key bindings: {}", e);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn defaults_bind_every_action() {
+        let bindings = ActionBindings::default();
+        for action in Action::ALL {
+            assert!(bindings.key_for(action).is_some());
+            assert!(bindings.button_for(action).is_some());
+        }
+        assert_eq!(
+            bindings.key_for(Action::Move(Axis::X, Direction::Positive)),
+            Some(KeyCode::W)
+        );
+    }
+
+    #[test]
+    fn binding_a_key_moves_it() {
+        let mut bindings = ActionBindings::default();
+        let up = Action::Move(Axis::X, Direction::Positive);
+        let down = Action::Move(Axis::X, Direction::Negative);
+
+        bindings.bind(up, KeyCode::S);
+        assert_eq!(bindings.key_for(up), Some(KeyCode::S));
+        assert_eq!(bindings.key_for(down), None);
+        assert_eq!(bindings.label(down, InputDevice::Keyboard), "");
+        assert_eq!(bindings.label(down, InputDevice::Gamepad), "v");
+    }
+
+    #[test]
+    fn stick_pushes_map_to_moves() {
+        assert_eq!(stick_action(0.1, -0.2), None);
+        assert_eq!(
+            stick_action(0.2, 0.9),
+            Some(Action::Move(Axis::X, Direction::Positive))
+        );
+        assert_eq!(
+            stick_action(-0.8, 0.3),
+            Some(Action::Move(Axis::Y, Direction::Negative))
+        );
+    }
+
+    #[test]
+    fn round_trips_through_file() {
+        let path = std::env::temp_dir().join("nothing-moves-bindings-test.ron");
+        let mut bindings = ActionBindings::default();
+        bindings.bind(
+            Action::ShiftAxis(Axis::Y, Direction::Positive),
+            KeyCode::Key1,
+        );
+
+        bindings.save(&path).unwrap();
+        assert_eq!(ActionBindings::load(&path).unwrap(), bindings);
+        fs::remove_file(path).unwrap();
+    }
+}
@@ -0,0 +1,128 @@
+use crate::AppState;
+use bevy::prelude::*;
+
+use super::{
+    asset::{LevelAssets, LevelPackProgress},
+    bindings::{Action, ActionBindings, ActionPressed, InputDevice},
+    loader::{LevelEntity, LevelInfo, LoadLevel},
+    maze_level::{LevelCompleted, MoveSucceeded},
+};
+
+/// Progress through the current level, reset whenever a level is loaded.
+#[derive(Default)]
+pub struct LevelStats {
+    pub moves: u32,
+    pub elapsed: f32,
+}
+
+pub fn track_level_time(time: Res<Time>, mut stats: ResMut<LevelStats>) {
+    stats.elapsed += time.delta_seconds();
+}
+
+pub fn count_moves(mut moves: EventReader<MoveSucceeded>, mut stats: ResMut<LevelStats>) {
+    stats.moves += moves.iter().count() as u32;
+}
+
+pub fn level_completed_listener(
+    mut completed: EventReader<LevelCompleted>,
+    mut app_state: ResMut<State<AppState>>,
+) {
+    if completed.iter().next().is_some() {
+        // Pushed rather than set, so the solved maze stays up behind the completion screen.
+        if let Err(e) = app_state.push(AppState::Completed) {
+            warn!("Couldn't show the completion screen: {}", e);
+        }
+    }
+}
+
+/// Moves on to the next level of the pack on confirm, or back to the main menu otherwise.
+pub fn completion_navigation(
+    mut actions: EventReader<ActionPressed>,
+    progress: Option<ResMut<LevelPackProgress>>,
+    assets: LevelAssets,
+    mut app_state: ResMut<State<AppState>>,
+    mut load_level: EventWriter<LoadLevel>,
+) {
+    let action = match actions.iter().next() {
+        Some(ActionPressed(action @ (Action::Confirm | Action::Back))) => *action,
+        _ => return,
+    };
+    let LevelAssets { packs, levels, .. } = assets;
+    let next = match (action, progress) {
+        (Action::Confirm, Some(mut progress)) => progress.start_next(&packs, &levels),
+        _ => None,
+    };
+    match next {
+        Some(level) => load_level.send(level),
+        None => {
+            if let Err(e) = app_state.replace(AppState::MainMenu) {
+                warn!("Couldn't return to the main menu: {}", e);
+            }
+        }
+    }
+}
+
+pub fn spawn_completion_screen(
+    mut c: Commands,
+    stats: Res<LevelStats>,
+    info: Res<LevelInfo>,
+    bindings: Res<ActionBindings>,
+    device: Res<InputDevice>,
+    assets: Res<AssetServer>,
+) {
+    let style = TextStyle {
+        font: assets.load("fonts\\UnicaOne-Regular.ttf"),
+        font_size: 50.0,
+        color: Color::WHITE,
+    };
+
+    let label = |s: String, font_size: f32| TextBundle {
+        text: Text::with_section(
+            s,
+            TextStyle {
+                font_size,
+                ..style.clone()
+            },
+            TextAlignment {
+                vertical: VerticalAlign::Center,
+                horizontal: HorizontalAlign::Center,
+            },
+        ),
+        ..default()
+    };
+
+    let minutes = (stats.elapsed / 60.0).floor();
+    let seconds = stats.elapsed - minutes * 60.0;
+
+    c.spawn_bundle(NodeBundle {
+        style: Style {
+            size: Size::new(Val::Percent(100.0), Val::Percent(100.0)),
+            flex_direction: FlexDirection::ColumnReverse,
+            justify_content: JustifyContent::Center,
+            align_items: AlignItems::Center,
+            ..default()
+        },
+        color: Color::rgba(0.0, 0.0, 0.0, 0.6).into(),
+        ..default()
+    })
+    .insert(LevelEntity)
+    .with_children(|c| {
+        c.spawn_bundle(label("Maze Complete".into(), 80.0));
+        if !info.title.is_empty() {
+            c.spawn_bundle(label(info.title.clone(), 50.0));
+        }
+        c.spawn_bundle(label(format!("Moves: {}", stats.moves), 50.0));
+        c.spawn_bundle(label(format!("Time: {}:{:05.2}", minutes, seconds), 50.0));
+        if let Some((seed, version)) = info.seed {
+            c.spawn_bundle(label(format!("Seed: {} ({})", seed, version), 30.0));
+        }
+        c.spawn_bundle(label(
+            format!(
+                "{} to continue, {} for the menu",
+                bindings.label(Action::Confirm, *device),
+                bindings.label(Action::Back, *device)
+            ),
+            30.0,
+        ));
+    });
+}
@@ -1,54 +1,54 @@
-use bevy::prelude::*;
+use crate::AppState;
+use bevy::{ecs::schedule::StateError, prelude::*};
 
+use super::bindings::{Action, ActionPressed};
 use super::maze_level::*;
-use super::maze_level::{Axis, Direction};
+use super::maze_renderer::AxisRotation;
 
 pub fn level_navigation(
     level: Option<ResMut<MazeLevel>>,
-    keys: Res<Input<KeyCode>>,
+    mut actions: EventReader<ActionPressed>,
+    mut rotation: ResMut<AxisRotation>,
     mut position_event: EventWriter<PositionChanged>,
-    mut axis_event: EventWriter<AxisChanged>,
+    mut succeeded_event: EventWriter<MoveSucceeded>,
+    mut blocked_event: EventWriter<MoveBlocked>,
+    mut completed_event: EventWriter<LevelCompleted>,
 ) {
     if let Some(mut level) = level {
-        if keys.just_pressed(KeyCode::Q) {
-            level.shift_axis(Axis::X, Direction::Negative);
-            axis_event.send(AxisChanged { axis: level.axis() });
+        for ActionPressed(action) in actions.iter() {
+            match *action {
+                Action::ShiftAxis(axis, dir) => rotation.queue(axis, dir),
+                Action::Move(axis, dir) => match level.move_pos(axis, dir) {
+                    MoveResult::Moved => {
+                        succeeded_event.send(MoveSucceeded);
+                        position_event.send(PositionChanged {
+                            position: level.pos(),
+                        });
+                        if level.at_goal() {
+                            completed_event.send(LevelCompleted);
+                        }
+                    }
+                    result => blocked_event.send(MoveBlocked { axis, dir, result }),
+                },
+                Action::Confirm | Action::Back => {}
+            }
         }
-        if keys.just_pressed(KeyCode::E) {
-            level.shift_axis(Axis::X, Direction::Positive);
-            axis_event.send(AxisChanged { axis: level.axis() });
-        }
-        if keys.just_pressed(KeyCode::Z) {
-            level.shift_axis(Axis::Y, Direction::Negative);
-            axis_event.send(AxisChanged { axis: level.axis() });
-        }
-        if keys.just_pressed(KeyCode::X) {
-            level.shift_axis(Axis::Y, Direction::Positive);
-            axis_event.send(AxisChanged { axis: level.axis() });
-        }
-        if keys.just_pressed(KeyCode::W) {
-            level.move_pos(Axis::X, Direction::Positive);
-            position_event.send(PositionChanged {
-                position: level.pos(),
-            });
-        }
-        if keys.just_pressed(KeyCode::S) {
-            level.move_pos(Axis::X, Direction::Negative);
-            position_event.send(PositionChanged {
-                position: level.pos(),
-            });
-        }
-        if keys.just_pressed(KeyCode::D) {
-            level.move_pos(Axis::Y, Direction::Positive);
-            position_event.send(PositionChanged {
-                position: level.pos(),
-            });
-        }
-        if keys.just_pressed(KeyCode::A) {
-            level.move_pos(Axis::Y, Direction::Negative);
-            position_event.send(PositionChanged {
-                position: level.pos(),
-            });
+    }
+}
+
+/// Pauses the level when the player backs out of it.
+pub fn pause_listener(
+    mut actions: EventReader<ActionPressed>,
+    mut app_state: ResMut<State<AppState>>,
+) {
+    if actions
+        .iter()
+        .any(|ActionPressed(action)| *action == Action::Back)
+    {
+        match app_state.push(AppState::Paused) {
+            // The level was completed this frame, which takes precedence.
+            Ok(()) | Err(StateError::StateAlreadyQueued) => {}
+            Err(e) => warn!("Couldn't pause the level: {}", e),
         }
     }
 }
@@ -1,39 +1,168 @@
-use crate::AppState;
+use crate::{
+    maze::{Braided, Coord, FixedPassages, Generator, GeneratorVersion, MazeGenerator},
+    AppState,
+};
 use bevy::prelude::*;
-use rand::prelude::*;
+use chrono::{Local, NaiveDate};
+use rand::{prelude::*, rngs::OsRng};
 
 use super::{
-    maze_level::{AxisChanged, PositionChanged},
+    completion::LevelStats,
+    maze_level::{AxisChanged, GoalPlacement, PositionChanged},
     MazeLevel,
 };
 
 #[derive(Clone, Debug)]
 pub struct LoadLevel {
-    pub rng_source: RngSource,
+    pub title: String,
+    pub layout: LayoutSource,
     pub dimensions: DimensionLength,
+    /// The cell the player starts in, the origin when empty.
+    pub start: Vec<Coord>,
+    /// Dimensions that wrap around from their last cell to their first, indexed by dimension.
+    pub wraps: Vec<bool>,
+    pub goal: GoalPlacement,
+}
+
+/// Where the passages of a level come from.
+#[derive(Clone, Debug)]
+pub enum LayoutSource {
+    /// Generated from a seed.
+    Generated {
+        rng_source: RngSource,
+        /// How the seed is turned into a maze, kept so the same seed always builds the same maze.
+        version: GeneratorVersion,
+        generator: Generator,
+        /// The fraction of dead ends to open into loops after generation.
+        braid: f32,
+    },
+    /// Passages stored with the level.
+    Stored(FixedPassages),
+}
+
+impl LayoutSource {
+    /// A layout generated with the latest version of the default generator.
+    pub fn generated(rng_source: RngSource) -> Self {
+        LayoutSource::Generated {
+            rng_source,
+            version: GeneratorVersion::LATEST,
+            generator: Generator::default(),
+            braid: 0.0,
+        }
+    }
+}
+
+impl Default for LayoutSource {
+    fn default() -> Self {
+        Self::generated(RngSource::Entropy)
+    }
+}
+
+/// Marks the top of each entity tree spawned for a level, which is despawned when the level is left.
+#[derive(Component)]
+pub struct LevelEntity;
+
+/// Details of the level currently loaded.
+pub struct LevelInfo {
+    pub title: String,
+    /// The seed the maze was generated from, whichever source it came from, and the version it
+    /// was generated with. Levels with stored passages weren't generated, so have none.
+    pub seed: Option<(u64, GeneratorVersion)>,
+    /// The request the level was built from, with its seed fixed so restarting gives the same maze.
+    pub source: LoadLevel,
 }
 
 #[derive(Clone, Debug)]
 pub enum RngSource {
     Seeded(u64),
+    /// A seed drawn from the OS, reported in [`LevelInfo`] so the maze can be shared.
+    Entropy,
+    /// Any text, hashed into a seed.
+    Phrase(String),
+    /// The same seed for everyone playing on the same local date.
+    Daily,
+}
+
+impl RngSource {
+    /// Picks the seed to generate from, drawing a new one each time for `Entropy`.
+    pub fn seed(&self) -> u64 {
+        match self {
+            RngSource::Seeded(seed) => *seed,
+            RngSource::Entropy => OsRng.next_u64(),
+            RngSource::Phrase(phrase) => phrase_seed(phrase),
+            RngSource::Daily => daily_seed(Local::now().date_naive()),
+        }
+    }
+}
+
+/// 64 bit FNV-1a, which unlike the std hashers is guaranteed not to change between builds.
+fn phrase_seed(phrase: &str) -> u64 {
+    phrase.bytes().fold(0xcbf29ce484222325, |hash, byte| {
+        (hash ^ byte as u64).wrapping_mul(0x100000001b3)
+    })
+}
+
+/// The seed of the daily maze for a date, shared by everyone on that date.
+fn daily_seed(date: NaiveDate) -> u64 {
+    phrase_seed(&date.format("%Y-%m-%d").to_string())
 }
 
-// Remove this once construction methods for dimensions are found.
-#[allow(dead_code)]
 #[derive(Clone, Debug)]
 pub enum DimensionLength {
-    Two([u8; 2]),
-    Three([u8; 3]),
-    Four([u8; 4]),
-    Five([u8; 5]),
-    Six([u8; 6]),
+    Two([Coord; 2]),
+    Three([Coord; 3]),
+    Four([Coord; 4]),
+    Five([Coord; 5]),
+    Six([Coord; 6]),
+    /// Any number of dimensions, for when there is no fixed size variant.
+    Dynamic(Vec<Coord>),
+}
+
+impl DimensionLength {
+    /// Picks the fixed size variant matching the number of lengths, if there is one.
+    pub fn from_lengths(lengths: &[Coord]) -> Self {
+        match lengths.len() {
+            2 => Self::Two(lengths.try_into().unwrap()),
+            3 => Self::Three(lengths.try_into().unwrap()),
+            4 => Self::Four(lengths.try_into().unwrap()),
+            5 => Self::Five(lengths.try_into().unwrap()),
+            6 => Self::Six(lengths.try_into().unwrap()),
+            _ => Self::Dynamic(lengths.to_vec()),
+        }
+    }
+}
+
+impl LoadLevel {
+    /// The same level shape with a fresh random seed, generating a new layout in place of a fixed one.
+    pub fn reseeded(&self) -> Self {
+        let layout = match &self.layout {
+            LayoutSource::Generated {
+                generator, braid, ..
+            } => LayoutSource::Generated {
+                rng_source: RngSource::Entropy,
+                version: GeneratorVersion::LATEST,
+                generator: generator.clone(),
+                braid: *braid,
+            },
+            LayoutSource::Stored(_) => LayoutSource::default(),
+        };
+        Self {
+            title: String::new(),
+            layout,
+            ..self.clone()
+        }
+    }
 }
 
 impl Default for LoadLevel {
     fn default() -> Self {
         Self {
-            rng_source: RngSource::Seeded(123456789),
+            title: String::new(),
+            layout: Default::default(),
             dimensions: DimensionLength::Two([2, 2]),
+            start: Vec::new(),
+            wraps: Vec::new(),
+            goal: Default::default(),
         }
     }
 }
@@ -44,17 +173,80 @@ pub fn level_load_system(
     mut app_state: ResMut<State<AppState>>,
 ) {
     for level_loader in events.iter() {
-        let mut rng = match level_loader.rng_source {
-            RngSource::Seeded(seed) => StdRng::seed_from_u64(seed),
+        let mut source = level_loader.clone();
+        let (maze, seed) = match &mut source.layout {
+            LayoutSource::Generated {
+                rng_source,
+                version,
+                generator,
+                braid,
+            } => {
+                let seed = rng_source.seed();
+                // Fixed in the source, so restarting gives the same maze.
+                *rng_source = RngSource::Seeded(seed);
+                let generator = Braided {
+                    generator: generator.clone(),
+                    density: *braid,
+                };
+                let maze = build_level(level_loader, &generator, &mut version.rng(seed));
+                (maze, Some((seed, *version)))
+            }
+            // Stored passages draw nothing from the rng.
+            LayoutSource::Stored(passages) => {
+                (build_level(level_loader, passages, &mut OsRng), None)
+            }
         };
-        c.insert_resource(match level_loader.dimensions {
-            DimensionLength::Two(lengths) => MazeLevel::new(&lengths, &mut rng),
-            DimensionLength::Three(lengths) => MazeLevel::new(&lengths, &mut rng),
-            DimensionLength::Four(lengths) => MazeLevel::new(&lengths, &mut rng),
-            DimensionLength::Five(lengths) => MazeLevel::new(&lengths, &mut rng),
-            DimensionLength::Six(lengths) => MazeLevel::new(&lengths, &mut rng),
+        let maze = match maze {
+            Some(maze) => maze,
+            None => {
+                warn!("Couldn't load the level, it needs at least two dimensions");
+                continue;
+            }
+        };
+        c.insert_resource(maze);
+        c.insert_resource(LevelInfo {
+            title: level_loader.title.clone(),
+            seed,
+            source,
         });
-        app_state.push(AppState::InMaze).unwrap();
+        c.insert_resource(LevelStats::default());
+        // Unwinds the whole stack, so a level loaded from the pause menu leaves the old one first.
+        if *app_state.current() == AppState::InMaze {
+            app_state.overwrite_restart();
+        } else {
+            app_state.overwrite_replace(AppState::InMaze).unwrap();
+        }
+    }
+}
+
+/// Builds the maze of a level from `generator`, or `None` if it has too few dimensions.
+fn build_level(
+    level: &LoadLevel,
+    generator: &impl MazeGenerator,
+    rng: &mut impl rand::Rng,
+) -> Option<MazeLevel> {
+    let start = &level.start;
+    let goal = &level.goal;
+    let wraps = &level.wraps;
+    match &level.dimensions {
+        DimensionLength::Two(lengths) => {
+            Some(MazeLevel::new(lengths, wraps, generator, start, goal, rng))
+        }
+        DimensionLength::Three(lengths) => {
+            Some(MazeLevel::new(lengths, wraps, generator, start, goal, rng))
+        }
+        DimensionLength::Four(lengths) => {
+            Some(MazeLevel::new(lengths, wraps, generator, start, goal, rng))
+        }
+        DimensionLength::Five(lengths) => {
+            Some(MazeLevel::new(lengths, wraps, generator, start, goal, rng))
+        }
+        DimensionLength::Six(lengths) => {
+            Some(MazeLevel::new(lengths, wraps, generator, start, goal, rng))
+        }
+        DimensionLength::Dynamic(lengths) => {
+            MazeLevel::new_dynamic(lengths, wraps, generator, start, goal, rng)
+        }
     }
 }
 
@@ -93,7 +285,14 @@ pub fn spawn_player(
         })),
         material: materials.add(Color::rgb(0.5, 0.5, 0.8).into()),
         ..Default::default()
-    });
+    })
+    .insert(LevelEntity);
+}
+
+pub fn despawn_level(mut c: Commands, entities: Query<Entity, With<LevelEntity>>) {
+    for entity in entities.iter() {
+        c.entity(entity).despawn_recursive();
+    }
 }
 
 #[derive(Component)]
@@ -122,3 +321,46 @@ impl MazeAssets {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reseeding_generates_fixed_layouts() {
+        let level = LoadLevel {
+            title: "Fixed".into(),
+            layout: LayoutSource::Stored(FixedPassages(Vec::new())),
+            ..default()
+        };
+        let reseeded = level.reseeded();
+        assert!(reseeded.title.is_empty());
+        assert!(matches!(
+            reseeded.layout,
+            LayoutSource::Generated {
+                rng_source: RngSource::Entropy,
+                generator: Generator::Kruskal(_),
+                ..
+            }
+        ));
+        assert!(matches!(reseeded.dimensions, DimensionLength::Two([2, 2])));
+    }
+
+    #[test]
+    fn phrase_seeds_are_stable() {
+        assert_eq!(phrase_seed(""), 0xcbf29ce484222325);
+        assert_eq!(phrase_seed("hello"), 0xa430d84680aabd0b);
+        assert_eq!(
+            RngSource::Phrase("nothing moves".into()).seed(),
+            phrase_seed("nothing moves")
+        );
+        assert_ne!(phrase_seed("2022-01-01"), phrase_seed("2022-01-02"));
+    }
+
+    #[test]
+    fn daily_seeds_hash_the_date() {
+        let date = NaiveDate::from_ymd_opt(2022, 1, 8).unwrap();
+        assert_eq!(daily_seed(date), phrase_seed("2022-01-08"));
+        assert_ne!(daily_seed(date), daily_seed(date.succ_opt().unwrap()));
+    }
+}
@@ -1,10 +1,15 @@
 use std::ops::{Deref, DerefMut};
 
-use crate::maze;
+use serde::{Deserialize, Serialize};
 
-struct MazeImpl<const DIMS: usize> {
-    maze: maze::Maze<DIMS>,
-    position: [u8; DIMS],
+use crate::maze::{self, Coord, NavigableMaze};
+
+pub use crate::maze::Direction;
+
+struct MazeImpl<M: NavigableMaze> {
+    maze: M,
+    position: M::Cell,
+    goal: M::Cell,
     axis: [u8; 2],
 }
 
@@ -15,10 +20,49 @@ pub struct AxisChanged {
 
 #[derive(Clone, Debug)]
 pub struct PositionChanged {
-    pub position: [u8; 2],
+    pub position: [Coord; 2],
+}
+
+#[derive(Clone, Debug)]
+pub struct LevelCompleted;
+
+/// Sent when the player moves to a neighboring cell, followed by a [`PositionChanged`] with
+/// where they moved to.
+#[derive(Clone, Debug)]
+pub struct MoveSucceeded;
+
+/// Sent when the player tries to move into a wall or off the edge of the maze.
+#[derive(Clone, Debug)]
+pub struct MoveBlocked {
+    pub axis: Axis,
+    pub dir: Direction,
+    /// What stopped the move, either `Blocked` by a wall or `OutOfBounds` at the edge.
+    pub result: MoveResult,
+}
+
+/// The outcome of trying to move the player or shift an axis.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MoveResult {
+    Moved,
+    /// A wall is in the way.
+    Blocked,
+    /// There is nothing further along the axis.
+    OutOfBounds,
+}
+
+/// Where the exit of a level is placed.
+#[derive(Clone, Debug, PartialEq, Eq, Default, Deserialize)]
+pub enum GoalPlacement {
+    /// The corner opposite the origin.
+    #[default]
+    FarCorner,
+    /// The cell that takes the most moves to reach from the start.
+    Farthest,
+    /// A specific cell, falling back to the far corner if it's outside of the maze.
+    At(Vec<Coord>),
 }
 
-#[derive(PartialEq, Eq, Clone, Copy)]
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Clone, Copy, Serialize, Deserialize)]
 pub enum Axis {
     X,
     Y,
@@ -46,47 +90,57 @@ impl Axis {
     }
 }
 
-#[derive(PartialEq, Eq, Clone, Copy)]
-pub enum Direction {
-    Positive,
-    Negative,
+fn shift_wrapped(dir: Direction, value: u8, limit: u8) -> u8 {
+    (match dir {
+        Direction::Positive => value.checked_add(1).unwrap_or(0),
+        Direction::Negative => value.checked_sub(1).unwrap_or(limit - 2),
+    } % (limit - 1))
 }
 
-impl Direction {
-    fn shift_wrapped(&self, value: u8, limit: u8) -> u8 {
-        (match self {
-            Direction::Positive => value.checked_add(1).unwrap_or(0),
-            Direction::Negative => value.checked_sub(1).unwrap_or(limit - 2),
-        } % (limit - 1))
-    }
+/// The cell at `coords`, if it is inside of the maze.
+fn cell_at<M: NavigableMaze>(maze: &M, coords: &[Coord]) -> Option<M::Cell> {
+    maze.grid().index_of(coords)?;
+    let mut cell = maze.origin();
+    cell.as_mut().copy_from_slice(coords);
+    Some(cell)
 }
 
-impl<const DIMS: usize> Default for MazeImpl<DIMS> {
-    fn default() -> Self {
-        Self {
-            maze: Default::default(),
-            axis: [0, 1],
-            position: [0; DIMS],
+impl<M: NavigableMaze> MazeImpl<M> {
+    /// Places the player at `start`, or the origin if `start` is outside of the maze.
+    pub fn new(maze: M, start: &[Coord], goal: &GoalPlacement) -> Self {
+        let position = cell_at(&maze, start).unwrap_or_else(|| maze.origin());
+        let mut corner = maze.origin();
+        for (dim, coord) in corner.as_mut().iter_mut().enumerate() {
+            let length = maze.lengths()[dim];
+            // Across a wrapped dimension the far side is halfway around.
+            *coord = if maze.wraps()[dim] {
+                length / 2
+            } else {
+                length.saturating_sub(1)
+            };
         }
-    }
-}
-
-impl<const DIMS: usize> MazeImpl<DIMS> {
-    pub fn new(lengths: &[u8; DIMS], rng: &mut impl rand::Rng) -> Self {
+        let goal = match goal {
+            GoalPlacement::FarCorner => corner,
+            GoalPlacement::Farthest => maze
+                .farthest_from(&position)
+                .unwrap_or_else(|| position.clone()),
+            GoalPlacement::At(coords) => cell_at(&maze, coords).unwrap_or(corner),
+        };
         Self {
-            maze: crate::maze::Maze::new(lengths, rng),
+            maze,
             axis: [0, 1],
-            position: [0; DIMS],
+            position,
+            goal,
         }
     }
 }
 
-impl<const DIMS: usize> MazeView for MazeImpl<DIMS> {
+impl<M: NavigableMaze> MazeView for MazeImpl<M> {
     fn axis(&self) -> [u8; 2] {
         self.axis
     }
 
-    fn shift_axis(&mut self, axis: Axis, dir: Direction) {
+    fn shift_axis(&mut self, axis: Axis, dir: Direction) -> MoveResult {
         let target_axis = *axis.get(&self.axis());
         let off_target_axis = *axis.invert().get(&self.axis());
 
@@ -96,90 +150,107 @@ impl<const DIMS: usize> MazeView for MazeImpl<DIMS> {
             target_axis
         };
 
-        let new_off_axis = dir.shift_wrapped(linear_current, DIMS as u8);
+        let new_off_axis = shift_wrapped(dir, linear_current, self.maze.lengths().len() as u8);
         let dest = if new_off_axis >= off_target_axis {
             new_off_axis + 1
         } else {
             new_off_axis
         };
 
+        // With only two dimensions there is no other axis to shift to.
+        if dest == target_axis {
+            return MoveResult::OutOfBounds;
+        }
         *axis.get_mut(&mut self.axis) = dest;
+        MoveResult::Moved
     }
 
-    fn dims_limit(&self) -> &[u8] {
+    fn dims_limit(&self) -> &[Coord] {
         self.maze.lengths()
     }
 
-    fn dims(&self) -> &[u8] {
-        &self.position
+    fn dims(&self) -> &[Coord] {
+        self.position.as_ref()
+    }
+
+    fn goal(&self) -> &[Coord] {
+        self.goal.as_ref()
+    }
+
+    fn at_goal(&self) -> bool {
+        self.position == self.goal
     }
 
     // assume dim_x and dim_y are both together.
-    fn pos_limit(&self) -> [u8; 2] {
+    fn pos_limit(&self) -> [Coord; 2] {
         [
             self.maze.lengths()[self.axis[0] as usize],
             self.maze.lengths()[self.axis[1] as usize],
         ]
     }
 
-    fn pos(&self) -> [u8; 2] {
+    fn pos_wraps(&self) -> [bool; 2] {
+        [
+            self.maze.wraps()[self.axis[0] as usize],
+            self.maze.wraps()[self.axis[1] as usize],
+        ]
+    }
+
+    fn pos(&self) -> [Coord; 2] {
         [
-            self.position[self.axis[0] as usize],
-            self.position[self.axis[1] as usize],
+            self.position.as_ref()[self.axis[0] as usize],
+            self.position.as_ref()[self.axis[1] as usize],
         ]
     }
 
-    fn move_pos(&mut self, axis: Axis, dir: Direction) {
+    fn move_pos(&mut self, axis: Axis, dir: Direction) -> MoveResult {
         let dim = *axis.get(&self.axis) as usize;
-        if let Some(true) = self.can_move(dim as u8, dir) {
-            if let Some(new_pos) = if dir == Direction::Positive {
-                self.position[dim].checked_add(1)
-            } else {
-                self.position[dim].checked_sub(1)
-            } {
-                self.position[dim] = new_pos;
-            }
+        if self.maze.neighbor(&self.position, dim, dir).is_none() {
+            return MoveResult::OutOfBounds;
+        }
+        if let Some(new_pos) = self.maze.step(&self.position, dim, dir) {
+            self.position = new_pos;
+            MoveResult::Moved
+        } else {
+            MoveResult::Blocked
         }
     }
 
     fn can_move(&self, dim: u8, dir: Direction) -> Option<bool> {
         let dim = dim as usize;
-        let mut pos = self.position;
-        if dir == Direction::Negative {
-            if let Some(new_pos) = pos[dim].checked_sub(1) {
-                pos[dim] = new_pos;
-            } else {
-                return None;
-            }
-        }
-        self.maze.can_move(&pos, dim)
+        self.maze.neighbor(&self.position, dim, dir)?;
+        Some(self.maze.step(&self.position, dim, dir).is_some())
     }
 
-    fn wall_in_current(&self, position: [u8; 2], axis: Axis) -> bool {
-        let mut cursor = self.position;
-        cursor[self.axis[0] as usize] = position[0];
-        cursor[self.axis[1] as usize] = position[1];
-        if let Some(walkable) = self.maze.can_move(&cursor, *axis.get(&self.axis) as usize) {
-            !walkable
-        } else {
-            false
-        }
+    fn wall_in_current(&self, position: [Coord; 2], axis: Axis) -> bool {
+        let mut cursor = self.position.clone();
+        cursor.as_mut()[self.axis[0] as usize] = position[0];
+        cursor.as_mut()[self.axis[1] as usize] = position[1];
+        let dim = *axis.get(&self.axis) as usize;
+        // Walls are drawn on the positive side of a cell, wherever no passage leads to the neighbor.
+        self.maze
+            .neighbor(&cursor, dim, Direction::Positive)
+            .is_some()
+            && self.maze.step(&cursor, dim, Direction::Positive).is_none()
     }
 }
 
 pub trait MazeView: Sync + Send {
     fn axis(&self) -> [u8; 2];
-    fn shift_axis(&mut self, axis: Axis, dir: Direction);
+    fn shift_axis(&mut self, axis: Axis, dir: Direction) -> MoveResult;
 
-    fn dims_limit(&self) -> &[u8];
-    fn dims(&self) -> &[u8];
-    fn pos_limit(&self) -> [u8; 2];
-    fn pos(&self) -> [u8; 2];
-    fn move_pos(&mut self, axis: Axis, dir: Direction);
+    fn dims_limit(&self) -> &[Coord];
+    fn dims(&self) -> &[Coord];
+    fn goal(&self) -> &[Coord];
+    fn at_goal(&self) -> bool;
+    fn pos_limit(&self) -> [Coord; 2];
+    fn pos_wraps(&self) -> [bool; 2];
+    fn pos(&self) -> [Coord; 2];
+    fn move_pos(&mut self, axis: Axis, dir: Direction) -> MoveResult;
 
     fn can_move(&self, dim: u8, dir: Direction) -> Option<bool>;
 
-    fn wall_in_current(&self, position: [u8; 2], axis: Axis) -> bool;
+    fn wall_in_current(&self, position: [Coord; 2], axis: Axis) -> bool;
 }
 
 pub struct MazeLevel {
@@ -189,17 +260,52 @@ pub struct MazeLevel {
 impl Default for MazeLevel {
     fn default() -> Self {
         Self {
-            inner: Box::new(MazeImpl::<2>::default()),
+            inner: Box::new(MazeImpl::new(
+                maze::Maze::<2>::default(),
+                &[],
+                &GoalPlacement::default(),
+            )),
         }
     }
 }
 
 impl MazeLevel {
-    pub fn new<const DIMS: usize>(lengths: &[u8; DIMS], rng: &mut impl rand::Rng) -> Self {
+    pub fn new<const DIMS: usize>(
+        lengths: &[Coord; DIMS],
+        wraps: &[bool],
+        generator: &impl maze::MazeGenerator,
+        start: &[Coord],
+        goal: &GoalPlacement,
+        rng: &mut impl rand::Rng,
+    ) -> Self {
+        let mut dim_wraps = [false; DIMS];
+        for (wrap, flag) in dim_wraps.iter_mut().zip(wraps) {
+            *wrap = *flag;
+        }
+        let maze = maze::Maze::with_wrapping(lengths, &dim_wraps, generator, rng);
         Self {
-            inner: Box::new(MazeImpl::new(lengths, rng)),
+            inner: Box::new(MazeImpl::new(maze, start, goal)),
         }
     }
+
+    /// Builds a level with any number of dimensions, without the fixed dimension fast path.
+    /// Returns `None` with fewer than two dimensions, as the view always shows two of them.
+    pub fn new_dynamic(
+        lengths: &[Coord],
+        wraps: &[bool],
+        generator: &impl maze::MazeGenerator,
+        start: &[Coord],
+        goal: &GoalPlacement,
+        rng: &mut impl rand::Rng,
+    ) -> Option<Self> {
+        if lengths.len() < 2 {
+            return None;
+        }
+        let maze = maze::DynMaze::with_wrapping(lengths, wraps, generator, rng);
+        Some(Self {
+            inner: Box::new(MazeImpl::new(maze, start, goal)),
+        })
+    }
 }
 
 impl Deref for MazeLevel {
@@ -217,7 +323,7 @@ impl DerefMut for MazeLevel {
 }
 
 impl MazeLevel {
-    pub fn iter_walls(&self) -> impl std::iter::Iterator<Item = ([u8; 2], [u8; 2])> + '_ {
+    pub fn iter_walls(&self) -> impl std::iter::Iterator<Item = ([Coord; 2], [Coord; 2])> + '_ {
         let [length_x, length_y] = self.pos_limit();
 
         (0..length_x)
@@ -239,3 +345,124 @@ impl MazeLevel {
             .flatten()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::maze::{DynMaze, FixedPassages, Kruskal, Maze, PassageRecord};
+    use rand::prelude::*;
+
+    fn corridor() -> MazeImpl<Maze<3>> {
+        // A single corridor along the first dimension, with one step up the second at the end.
+        let passages = FixedPassages(vec![
+            PassageRecord {
+                cell: vec![0, 0, 0],
+                dim: 0,
+            },
+            PassageRecord {
+                cell: vec![1, 0, 0],
+                dim: 1,
+            },
+        ]);
+        let mut rng = StdRng::seed_from_u64(684153987);
+        let maze = Maze::with_generator(&[2, 2, 1], &passages, &mut rng);
+        MazeImpl::new(maze, &[], &GoalPlacement::default())
+    }
+
+    #[test]
+    fn move_pos_reports_result() {
+        let mut level = corridor();
+
+        assert_eq!(
+            level.move_pos(Axis::X, Direction::Negative),
+            MoveResult::OutOfBounds
+        );
+        assert_eq!(
+            level.move_pos(Axis::Y, Direction::Positive),
+            MoveResult::Blocked
+        );
+        assert_eq!(level.pos(), [0, 0]);
+
+        assert_eq!(
+            level.move_pos(Axis::X, Direction::Positive),
+            MoveResult::Moved
+        );
+        assert_eq!(level.pos(), [1, 0]);
+        assert_eq!(
+            level.move_pos(Axis::Y, Direction::Positive),
+            MoveResult::Moved
+        );
+        assert!(level.at_goal());
+    }
+
+    #[test]
+    fn shift_axis_reports_result() {
+        let mut level = corridor();
+        assert_eq!(
+            level.shift_axis(Axis::Y, Direction::Positive),
+            MoveResult::Moved
+        );
+        assert_eq!(level.axis(), [0, 2]);
+
+        let mut flat = MazeImpl::new(Maze::<2>::default(), &[], &GoalPlacement::default());
+        assert_eq!(
+            flat.shift_axis(Axis::X, Direction::Positive),
+            MoveResult::OutOfBounds
+        );
+        assert_eq!(flat.axis(), [0, 1]);
+    }
+
+    #[test]
+    fn walks_long_corridors() {
+        let mut rng = StdRng::seed_from_u64(684153987);
+        let maze = Maze::new(&[1000, 1, 1], &mut rng);
+        let mut level = MazeImpl::new(maze, &[998, 0, 0], &GoalPlacement::default());
+        assert_eq!(level.goal(), &[999, 0, 0]);
+        assert_eq!(level.pos_limit(), [1000, 1]);
+
+        assert_eq!(
+            level.move_pos(Axis::X, Direction::Positive),
+            MoveResult::Moved
+        );
+        assert_eq!(level.pos(), [999, 0]);
+        assert!(level.at_goal());
+        assert_eq!(
+            level.move_pos(Axis::X, Direction::Positive),
+            MoveResult::OutOfBounds
+        );
+    }
+
+    #[test]
+    fn dynamic_levels_need_two_dimensions() {
+        let mut rng = StdRng::seed_from_u64(684153987);
+        let build = |lengths: &[Coord], rng: &mut StdRng| {
+            MazeLevel::new_dynamic(lengths, &[], &Kruskal, &[], &GoalPlacement::default(), rng)
+        };
+        assert!(build(&[], &mut rng).is_none());
+        assert!(build(&[5], &mut rng).is_none());
+        assert_eq!(build(&[5, 3], &mut rng).unwrap().pos_limit(), [5, 3]);
+    }
+
+    #[test]
+    fn shift_axis_keeps_axes_distinct() {
+        let mut rng = StdRng::seed_from_u64(684153987);
+        for dims in 2..=8 {
+            let maze = DynMaze::new(&vec![2; dims], &mut rng);
+            let mut level = MazeImpl::new(maze, &[], &GoalPlacement::default());
+            for _ in 0..200 {
+                let axis = if rng.gen() { Axis::X } else { Axis::Y };
+                let dir = if rng.gen() {
+                    Direction::Positive
+                } else {
+                    Direction::Negative
+                };
+                let result = level.shift_axis(axis, dir);
+
+                let [x, y] = level.axis();
+                assert_ne!(x, y, "{} dimensions", dims);
+                assert!((x as usize) < dims && (y as usize) < dims);
+                assert_eq!(result == MoveResult::OutOfBounds, dims == 2);
+            }
+        }
+    }
+}
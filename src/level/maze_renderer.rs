@@ -1,45 +1,226 @@
-use std::f32::consts::PI;
+use std::{collections::VecDeque, f32::consts::PI};
 
-use super::{loader::MazeAssets, maze_level::*};
+use super::maze_level::{Axis, Direction};
+use super::{
+    loader::{LevelEntity, MazeAssets},
+    maze_level::*,
+};
+use crate::maze::Coord;
 use bevy::prelude::*;
 
-pub fn spawn_maze_root(mut c: Commands) {
+pub fn spawn_maze_root(mut c: Commands, mut rotation: ResMut<AxisRotation>) {
+    rotation.reset();
     c.spawn_bundle(MazeRendererBundle {
-        renderer: MazeRenderer { last_axis: [0, 0] },
+        renderer: MazeRenderer { last_axis: None },
+        motion: Default::default(),
         transform: Default::default(),
         global_transform: Default::default(),
-    });
+    })
+    .insert(LevelEntity);
 }
 
 #[derive(Bundle)]
 pub struct MazeRendererBundle {
     pub renderer: MazeRenderer,
+    pub motion: MazeMotion,
     pub transform: Transform,
     pub global_transform: GlobalTransform,
 }
 
 #[derive(Component)]
 pub struct MazeRenderer {
-    last_axis: [u8; 2],
+    last_axis: Option<[u8; 2]>,
+}
+
+/// The walls of the maze as seen along one pair of axes.
+#[derive(Component)]
+pub struct MazeSlice;
+
+/// Folds a slice between two rotations around the player's cell.
+#[derive(Component)]
+pub struct SliceTransition {
+    from: Quat,
+    to: Quat,
+    /// The player's cell within this slice.
+    pivot: Vec3,
+    /// Where the pivot is held in the maze while rotating.
+    target: Vec3,
+    elapsed: f32,
+    duration: f32,
+    despawn_on_end: bool,
+}
+
+impl SliceTransition {
+    fn transform(&self, t: f32) -> Transform {
+        let rotation = self.from.slerp(self.to, t);
+        Transform::from_translation(self.target - rotation * self.pivot).with_rotation(rotation)
+    }
+}
+
+/// Axis shifts waiting on the fold animation, applied one at a time.
+pub struct AxisRotation {
+    /// How long each fold between slices takes, in seconds.
+    pub duration: f32,
+    remaining: f32,
+    queued: VecDeque<(Axis, Direction)>,
+}
+
+impl Default for AxisRotation {
+    fn default() -> Self {
+        Self {
+            duration: 0.4,
+            remaining: 0.0,
+            queued: VecDeque::new(),
+        }
+    }
+}
+
+impl AxisRotation {
+    pub fn queue(&mut self, axis: Axis, dir: Direction) {
+        self.queued.push_back((axis, dir));
+    }
+
+    fn reset(&mut self) {
+        self.remaining = 0.0;
+        self.queued.clear();
+    }
+}
+
+pub fn apply_axis_rotations(
+    time: Res<Time>,
+    mut level: ResMut<MazeLevel>,
+    mut rotation: ResMut<AxisRotation>,
+    mut axis_event: EventWriter<AxisChanged>,
+) {
+    rotation.remaining = (rotation.remaining - time.delta_seconds()).max(0.0);
+    if rotation.remaining > 0.0 {
+        return;
+    }
+    while let Some((axis, dir)) = rotation.queued.pop_front() {
+        if level.shift_axis(axis, dir) == MoveResult::Moved {
+            axis_event.send(AxisChanged { axis: level.axis() });
+            rotation.remaining = rotation.duration;
+            break;
+        }
+    }
+}
+
+pub fn animate_slice_transitions(
+    time: Res<Time>,
+    mut c: Commands,
+    mut slices: Query<(Entity, &mut Transform, &mut SliceTransition)>,
+) {
+    for (entity, mut transform, mut transition) in slices.iter_mut() {
+        transition.elapsed += time.delta_seconds();
+        let t = (transition.elapsed / transition.duration).min(1.0);
+        // Ease in and out, so the fold settles rather than snapping into place.
+        *transform = transition.transform(t * t * (3.0 - 2.0 * t));
+
+        if t >= 1.0 {
+            if transition.despawn_on_end {
+                c.entity(entity).despawn_recursive();
+            } else {
+                *transform = Transform::identity();
+                c.entity(entity).remove::<SliceTransition>();
+            }
+        }
+    }
+}
+
+const MOVE_DURATION: f32 = 0.15;
+const BUMP_DURATION: f32 = 0.25;
+const BUMP_DISTANCE: f32 = 0.15;
+
+/// Eases the maze root toward the player's position, and bumps it when a move is blocked.
+#[derive(Component, Default)]
+pub struct MazeMotion {
+    from: Vec3,
+    to: Vec3,
+    elapsed: f32,
+    /// The direction of the last blocked move, or zero if there is none.
+    bump: Vec3,
+    bump_elapsed: f32,
+}
+
+impl MazeMotion {
+    fn move_to(&mut self, to: Vec3) {
+        self.from = self.offset();
+        self.to = to;
+        self.elapsed = 0.0;
+    }
+
+    fn snap_to(&mut self, to: Vec3) {
+        self.from = to;
+        self.to = to;
+        self.elapsed = MOVE_DURATION;
+    }
+
+    fn offset(&self) -> Vec3 {
+        let t = (self.elapsed / MOVE_DURATION).min(1.0);
+        // Ease out, so the maze starts moving at once and settles gently.
+        let eased = 1.0 - (1.0 - t) * (1.0 - t);
+        self.from.lerp(self.to, eased)
+    }
+
+    fn bump_offset(&self) -> Vec3 {
+        let t = (self.bump_elapsed / BUMP_DURATION).min(1.0);
+        // Lunge toward the wall, then shake back to rest.
+        self.bump * BUMP_DISTANCE * (t * PI * 3.0).sin() * (1.0 - t)
+    }
+}
+
+fn maze_offset(p: [Coord; 2]) -> Vec3 {
+    Vec3::new(-(p[0] as f32), 0.0, -(p[1] as f32))
 }
 
 pub fn update_maze_offset(
     level: Res<MazeLevel>,
-    mut maze_query: Query<(&MazeRenderer, &mut Transform)>,
+    mut maze_query: Query<&mut MazeMotion>,
     mut position_changed: EventReader<PositionChanged>,
     mut axis_changed: EventReader<AxisChanged>,
+    mut move_blocked: EventReader<MoveBlocked>,
 ) {
-    let mut update_pos = || {
-        for (_, mut trs) in maze_query.iter_mut() {
-            let p = level.pos();
-            trs.translation = Vec3::new(-(p[0] as f32), 0.0, -(p[1] as f32))
+    for changed in position_changed.iter() {
+        for mut motion in maze_query.iter_mut() {
+            motion.move_to(maze_offset(changed.position));
         }
-    };
-    for _ in position_changed.iter() {
-        update_pos();
     }
+    // The fold between slices already shows the shift, so the maze jumps straight to it.
     for _ in axis_changed.iter() {
-        update_pos();
+        for mut motion in maze_query.iter_mut() {
+            motion.snap_to(maze_offset(level.pos()));
+        }
+    }
+    for blocked in move_blocked.iter() {
+        let sign = match blocked.dir {
+            Direction::Positive => 1.0,
+            Direction::Negative => -1.0,
+        };
+        // There's no wall to hit at the edge of the maze, so it only nudges.
+        let strength = match blocked.result {
+            MoveResult::OutOfBounds => 0.5,
+            _ => 1.0,
+        };
+        // The maze moves opposite to the player, so bumping the wall pulls it back.
+        let bump = match blocked.axis {
+            Axis::X => Vec3::new(-sign, 0.0, 0.0),
+            Axis::Y => Vec3::new(0.0, 0.0, -sign),
+        } * strength;
+        for mut motion in maze_query.iter_mut() {
+            motion.bump = bump;
+            motion.bump_elapsed = 0.0;
+        }
+    }
+}
+
+pub fn animate_maze_offset(
+    time: Res<Time>,
+    mut maze_query: Query<(&mut MazeMotion, &mut Transform)>,
+) {
+    for (mut motion, mut transform) in maze_query.iter_mut() {
+        motion.elapsed += time.delta_seconds();
+        motion.bump_elapsed += time.delta_seconds();
+        transform.translation = motion.offset() + motion.bump_offset();
     }
 }
 
@@ -47,54 +228,123 @@ pub fn maze_level_renderer(
     level: Res<MazeLevel>,
     assets: Res<MazeAssets>,
     mut commands: Commands,
+    rotation: Res<AxisRotation>,
     mut render_query: Query<(Entity, &mut MazeRenderer)>,
+    slice_query: Query<(Entity, Option<&SliceTransition>), With<MazeSlice>>,
     mut axis_changed: EventReader<AxisChanged>,
 ) {
     for _ in axis_changed.iter() {
         for (entity, mut renderer) in render_query.iter_mut() {
-            if renderer.last_axis == level.axis() {
-                continue;
+            let last_axis = match renderer.last_axis {
+                Some(last_axis) if last_axis == level.axis() => continue,
+                last_axis => last_axis,
+            };
+            renderer.last_axis = Some(level.axis());
+
+            let axis = level.axis();
+            let dims = level.dims();
+            let cell_in = |axis: [u8; 2]| {
+                Vec3::new(
+                    dims[axis[0] as usize] as f32,
+                    0.0,
+                    dims[axis[1] as usize] as f32,
+                )
+            };
+            let target = cell_in(axis);
+
+            // The first slice of a level appears in place, later ones fold in from the shifted axis.
+            let mut incoming = None;
+            if let Some(last_axis) = last_axis {
+                let fold = |angle: f32| {
+                    if last_axis[0] != axis[0] {
+                        Quat::from_rotation_z(angle)
+                    } else {
+                        Quat::from_rotation_x(angle)
+                    }
+                };
+                let changed = if last_axis[0] != axis[0] { 0 } else { 1 };
+                let angle = if axis[changed] > last_axis[changed] {
+                    PI / 2.0
+                } else {
+                    -PI / 2.0
+                };
+
+                for (outgoing, transition) in slice_query.iter() {
+                    if transition.is_some_and(|t| t.despawn_on_end) {
+                        continue;
+                    }
+                    commands.entity(outgoing).insert(SliceTransition {
+                        from: Quat::IDENTITY,
+                        to: fold(angle),
+                        pivot: cell_in(last_axis),
+                        target,
+                        elapsed: 0.0,
+                        duration: rotation.duration,
+                        despawn_on_end: true,
+                    });
+                }
+
+                incoming = Some(SliceTransition {
+                    from: fold(-angle),
+                    to: Quat::IDENTITY,
+                    pivot: target,
+                    target,
+                    elapsed: 0.0,
+                    duration: rotation.duration,
+                    despawn_on_end: false,
+                });
             }
-            renderer.last_axis = level.axis();
 
-            let mut entity = commands.entity(entity);
-            entity.despawn_descendants();
+            let mut slice = commands.spawn_bundle(TransformBundle::default());
+            slice.insert(MazeSlice);
+            if let Some(incoming) = incoming {
+                slice.insert(incoming.transform(0.0)).insert(incoming);
+            }
+            let slice = slice.id();
+            commands.entity(entity).add_child(slice);
+
+            let mut entity = commands.entity(slice);
             entity.with_children(|builder| {
-                // borders
+                // borders, wrapped axes have no border as their walls continue on the far side.
                 let [px, py] = level.pos_limit();
+                let [wrap_x, wrap_y] = level.pos_wraps();
                 let lx = px as f32;
                 let ly = py as f32;
-                builder.spawn_bundle(
-                    assets.wall(
-                        Transform::from_xyz((lx / 2.0) - 0.5, 0.0, -0.5)
-                            .with_scale(Vec3::new(1.0, 1.0, lx))
-                            .with_rotation(Quat::from_rotation_y(PI / 2.0)),
-                    ),
-                );
-                builder.spawn_bundle(
-                    assets.wall(
-                        Transform::from_xyz((lx / 2.0) - 0.5, 0.0, ly - 0.5)
-                            .with_scale(Vec3::new(1.0, 1.0, lx))
-                            .with_rotation(Quat::from_rotation_y(PI / 2.0)),
-                    ),
-                );
-                builder.spawn_bundle(
-                    assets.wall(
-                        Transform::from_xyz(-0.5, 0.0, (ly / 2.0) - 0.5)
-                            .with_scale(Vec3::new(1.0, 1.0, ly)),
-                    ),
-                );
-                builder.spawn_bundle(
-                    assets.wall(
-                        Transform::from_xyz(lx - 0.5, 0.0, (ly / 2.0) - 0.5)
-                            .with_scale(Vec3::new(1.0, 1.0, ly)),
-                    ),
-                );
+                if !wrap_y {
+                    builder.spawn_bundle(
+                        assets.wall(
+                            Transform::from_xyz((lx / 2.0) - 0.5, 0.0, -0.5)
+                                .with_scale(Vec3::new(1.0, 1.0, lx))
+                                .with_rotation(Quat::from_rotation_y(PI / 2.0)),
+                        ),
+                    );
+                    builder.spawn_bundle(
+                        assets.wall(
+                            Transform::from_xyz((lx / 2.0) - 0.5, 0.0, ly - 0.5)
+                                .with_scale(Vec3::new(1.0, 1.0, lx))
+                                .with_rotation(Quat::from_rotation_y(PI / 2.0)),
+                        ),
+                    );
+                }
+                if !wrap_x {
+                    builder.spawn_bundle(
+                        assets.wall(
+                            Transform::from_xyz(-0.5, 0.0, (ly / 2.0) - 0.5)
+                                .with_scale(Vec3::new(1.0, 1.0, ly)),
+                        ),
+                    );
+                    builder.spawn_bundle(
+                        assets.wall(
+                            Transform::from_xyz(lx - 0.5, 0.0, (ly / 2.0) - 0.5)
+                                .with_scale(Vec3::new(1.0, 1.0, ly)),
+                        ),
+                    );
+                }
 
                 // joints
                 let [psx, psy] = level.pos_limit();
-                for x in 0..psx + 1 {
-                    for y in 0..psy + 1 {
+                for x in 0..=psx {
+                    for y in 0..=psy {
                         builder.spawn_bundle(assets.joint(Transform::from_xyz(
                             x as f32 - 0.5,
                             0.0,
@@ -116,8 +366,80 @@ pub fn maze_level_renderer(
                     builder.spawn_bundle(
                         assets.wall(Transform::from_translation(position).with_rotation(rotation)),
                     );
+
+                    // Walls across a wrapped edge are shown on both sides of the slice.
+                    let wrapped_offset = if v2[0] == px {
+                        Some(Vec3::new(lx, 0.0, 0.0))
+                    } else if v2[1] == py {
+                        Some(Vec3::new(0.0, 0.0, ly))
+                    } else {
+                        None
+                    };
+                    if let Some(offset) = wrapped_offset {
+                        builder.spawn_bundle(assets.wall(
+                            Transform::from_translation(position - offset).with_rotation(rotation),
+                        ));
+                    }
                 }
             });
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn maze_motion_settles_on_target() {
+        let mut motion = MazeMotion::default();
+        motion.move_to(Vec3::new(-2.0, 0.0, 0.0));
+        assert_eq!(motion.offset(), Vec3::ZERO);
+
+        motion.elapsed = MOVE_DURATION / 2.0;
+        let halfway = motion.offset();
+        assert!(halfway.x < -1.0 && halfway.x > -2.0);
+
+        // Retargeting mid move continues from where the maze currently is.
+        motion.move_to(Vec3::new(-2.0, 0.0, -1.0));
+        assert_eq!(motion.offset(), halfway);
+
+        motion.elapsed = MOVE_DURATION;
+        assert_eq!(motion.offset(), Vec3::new(-2.0, 0.0, -1.0));
+    }
+
+    #[test]
+    fn bump_returns_to_rest() {
+        let mut motion = MazeMotion {
+            bump: Vec3::X,
+            ..Default::default()
+        };
+        assert_eq!(motion.bump_offset(), Vec3::ZERO);
+
+        motion.bump_elapsed = BUMP_DURATION / 6.0;
+        assert!(motion.bump_offset().x > 0.0);
+
+        motion.bump_elapsed = BUMP_DURATION;
+        assert!(motion.bump_offset().length() < 1e-5);
+    }
+
+    #[test]
+    fn slice_transition_holds_pivot_in_place() {
+        let transition = SliceTransition {
+            from: Quat::IDENTITY,
+            to: Quat::from_rotation_z(PI / 2.0),
+            pivot: Vec3::new(3.0, 0.0, 1.0),
+            target: Vec3::new(5.0, 0.0, 1.0),
+            elapsed: 0.0,
+            duration: 1.0,
+            despawn_on_end: true,
+        };
+
+        for t in [0.0, 0.25, 0.5, 1.0] {
+            let moved = transition.transform(t) * transition.pivot;
+            assert!(moved.distance(transition.target) < 1e-5);
+        }
+        let end = transition.transform(1.0).rotation;
+        assert!(end.abs_diff_eq(transition.to, 1e-5));
+    }
+}
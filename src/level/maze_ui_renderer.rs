@@ -1,4 +1,7 @@
+use super::bindings::{Action, ActionBindings, InputDevice};
+use super::loader::{LevelEntity, LevelInfo};
 use super::maze_level::{self, *};
+use super::maze_level::{Axis, Direction};
 use bevy::prelude::*;
 
 // Current dimension status text layout:
@@ -30,7 +33,14 @@ use bevy::prelude::*;
 //   (false, None) -> Greyed out circle,
 // }
 
-pub fn spawn_ui(mut c: Commands, maze: Res<MazeLevel>, assets: Res<AssetServer>) {
+pub fn spawn_ui(
+    mut c: Commands,
+    maze: Res<MazeLevel>,
+    info: Res<LevelInfo>,
+    bindings: Res<ActionBindings>,
+    device: Res<InputDevice>,
+    assets: Res<AssetServer>,
+) {
     let style = TextStyle {
         font: assets.load("fonts\\UnicaOne-Regular.ttf"),
         font_size: 50.0,
@@ -96,6 +106,7 @@ pub fn spawn_ui(mut c: Commands, maze: Res<MazeLevel>, assets: Res<AssetServer>)
         color: Color::NONE.into(),
         ..default()
     })
+    .insert(LevelEntity)
     .with_children(|c| {
         c.spawn_bundle(NodeBundle {
             style: Style {
@@ -111,7 +122,7 @@ pub fn spawn_ui(mut c: Commands, maze: Res<MazeLevel>, assets: Res<AssetServer>)
             // Axis Shift Controls
             c.spawn_bundle(TextBundle {
                 text: Text::with_section(
-                    "Z<W/S>X",
+                    controls_text(&bindings, *device, Axis::X),
                     style.clone(),
                     TextAlignment {
                         vertical: VerticalAlign::Center,
@@ -126,10 +137,11 @@ pub fn spawn_ui(mut c: Commands, maze: Res<MazeLevel>, assets: Res<AssetServer>)
                     ..default()
                 },
                 ..default()
-            });
+            })
+            .insert(AxisControlsLabel { axis: Axis::X });
             c.spawn_bundle(TextBundle {
                 text: Text::with_section(
-                    "Q<D/A>E",
+                    controls_text(&bindings, *device, Axis::Y),
                     style.clone(),
                     TextAlignment {
                         vertical: VerticalAlign::Center,
@@ -144,7 +156,8 @@ pub fn spawn_ui(mut c: Commands, maze: Res<MazeLevel>, assets: Res<AssetServer>)
                     ..default()
                 },
                 ..default()
-            });
+            })
+            .insert(AxisControlsLabel { axis: Axis::Y });
         });
 
         c.spawn_bundle(NodeBundle {
@@ -178,6 +191,10 @@ pub fn spawn_ui(mut c: Commands, maze: Res<MazeLevel>, assets: Res<AssetServer>)
             }
             c.spawn_bundle(label("]", Color::WHITE));
         });
+
+        if let Some((seed, version)) = info.seed {
+            c.spawn_bundle(label(&format!("Seed {} ({})", seed, version), Color::GRAY));
+        }
     });
 }
 
@@ -211,25 +228,56 @@ pub fn maze_axis_label_background_updater(
 }
 
 pub fn maze_axis_label_update_listener(
+    level: Res<MazeLevel>,
+    bindings: Res<ActionBindings>,
+    device: Res<InputDevice>,
     mut query: Query<(&MazeAxisLabel, &mut Text)>,
     mut axis_changed: EventReader<AxisChanged>,
 ) {
-    for changed in axis_changed.iter() {
-        for (label, mut text) in query.iter_mut() {
-            if changed.axis[0] == label.dim {
-                text.sections[0].value = match label.dir {
-                    maze_level::Direction::Positive => "W".into(),
-                    maze_level::Direction::Negative => "S".into(),
-                };
-            } else if changed.axis[1] == label.dim {
-                text.sections[0].value = match label.dir {
-                    maze_level::Direction::Positive => "D".into(),
-                    maze_level::Direction::Negative => "A".into(),
-                };
-            } else {
-                text.sections[0].value = "".into();
-            }
-        }
+    let axis = match axis_changed.iter().last() {
+        Some(changed) => changed.axis,
+        None if bindings.is_changed() || device.is_changed() => level.axis(),
+        None => return,
+    };
+    for (label, mut text) in query.iter_mut() {
+        text.sections[0].value = if axis[0] == label.dim {
+            bindings.label(Action::Move(Axis::X, label.dir), *device)
+        } else if axis[1] == label.dim {
+            bindings.label(Action::Move(Axis::Y, label.dir), *device)
+        } else {
+            "".into()
+        };
+    }
+}
+
+/// The keys that move along an axis, around the keys that shift the other one.
+#[derive(Component)]
+pub struct AxisControlsLabel {
+    axis: Axis,
+}
+
+/// The moves along `axis`, between the keys shifting the other axis, as in "Z<W/S>X".
+fn controls_text(bindings: &ActionBindings, device: InputDevice, axis: Axis) -> String {
+    let label = |action| bindings.label(action, device);
+    format!(
+        "{}<{}/{}>{}",
+        label(Action::ShiftAxis(axis.invert(), Direction::Negative)),
+        label(Action::Move(axis, Direction::Positive)),
+        label(Action::Move(axis, Direction::Negative)),
+        label(Action::ShiftAxis(axis.invert(), Direction::Positive)),
+    )
+}
+
+pub fn axis_controls_label_updater(
+    bindings: Res<ActionBindings>,
+    device: Res<InputDevice>,
+    mut query: Query<(&AxisControlsLabel, &mut Text)>,
+) {
+    if !bindings.is_changed() && !device.is_changed() {
+        return;
+    }
+    for (label, mut text) in query.iter_mut() {
+        text.sections[0].value = controls_text(&bindings, *device, label.axis);
     }
 }
 
@@ -249,12 +297,11 @@ pub fn maze_position_label_update_listener(
                 if let Some(target) = maze.dims().get(label.dimension) {
                     let position = target + 1;
                     section.value = format!("{}", position);
-                    section.style.color =
-                        if maze.dims_limit().get(label.dimension) == Some(&position) {
-                            Color::LIME_GREEN
-                        } else {
-                            Color::WHITE
-                        };
+                    section.style.color = if maze.goal().get(label.dimension) == Some(target) {
+                        Color::LIME_GREEN
+                    } else {
+                        Color::WHITE
+                    };
                 }
             }
         }
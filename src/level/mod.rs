@@ -1,3 +1,6 @@
+mod asset;
+mod bindings;
+mod completion;
 mod input;
 mod loader;
 mod maze_level;
@@ -7,5 +10,9 @@ mod plugin;
 
 use maze_level::MazeLevel;
 
-pub use loader::{DimensionLength, LoadLevel};
+pub use asset::{LevelAssets, LevelPack, LevelPackProgress, MazeLevelAsset, PROGRESS_PATH};
+pub use bindings::{Action, ActionBindings, ActionPressed, InputDevice};
+pub use loader::{DimensionLength, LayoutSource, LevelInfo, LoadLevel, RngSource};
+pub use maze_level::{Axis, Direction, GoalPlacement};
+pub use maze_renderer::AxisRotation;
 pub use plugin::LevelPlugin;
@@ -5,32 +5,222 @@ use bevy::prelude::*;
 #[derive(Debug, Clone, PartialEq, Eq, Hash, SystemLabel)]
 struct LevelInit;
 
+/// Moves the player, which may complete the level.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, SystemLabel)]
+struct Navigation;
+
+/// Leaves the level for the completion screen, before a pause in the same frame can.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, SystemLabel)]
+struct Completion;
+
 pub struct LevelPlugin;
 
 impl Plugin for LevelPlugin {
     fn build(&self, app: &mut App) {
-        app.add_startup_system(loader::load_maze_assets)
+        app.add_asset::<asset::MazeLevelAsset>()
+            .add_asset::<asset::LevelPack>()
+            .init_asset_loader::<asset::MazeLevelAssetLoader>()
+            .init_asset_loader::<asset::LevelPackLoader>()
+            .init_resource::<maze_renderer::AxisRotation>()
+            .add_startup_system(loader::load_maze_assets)
+            .init_resource::<bindings::InputDevice>()
+            .add_event::<bindings::ActionPressed>()
+            .add_startup_system(bindings::load_bindings)
+            .add_system(bindings::save_changed_bindings)
+            .add_system(bindings::read_actions.before(LevelInit))
+            .add_system(bindings::read_stick_actions.before(LevelInit))
             .add_system(loader::level_load_system.before(LevelInit))
             .add_event::<loader::LoadLevel>()
             .add_event::<maze_level::AxisChanged>()
             .add_event::<maze_level::PositionChanged>()
+            .add_event::<maze_level::LevelCompleted>()
+            .add_event::<maze_level::MoveBlocked>()
+            .add_event::<maze_level::MoveSucceeded>()
             .add_system_set(
                 SystemSet::on_enter(AppState::InMaze)
                     .label(LevelInit)
                     .with_system(maze_renderer::spawn_maze_root)
                     .with_system(maze_ui_renderer::spawn_ui)
                     .with_system(loader::initial_events_on_load)
-                    .with_system(loader::spawn_player),
+                    .with_system(loader::spawn_player)
+                    .with_system(asset::record_level_seed)
+                    .with_system(bindings::discard_actions),
             )
             .add_system_set(
                 SystemSet::on_update(AppState::InMaze)
                     .after(LevelInit)
                     .with_system(maze_ui_renderer::maze_axis_label_update_listener)
+                    .with_system(maze_ui_renderer::axis_controls_label_updater)
                     .with_system(maze_ui_renderer::maze_position_label_update_listener)
                     .with_system(maze_ui_renderer::maze_axis_label_background_updater)
                     .with_system(maze_renderer::maze_level_renderer)
                     .with_system(maze_renderer::update_maze_offset)
-                    .with_system(input::level_navigation),
+                    .with_system(maze_renderer::animate_maze_offset)
+                    .with_system(maze_renderer::apply_axis_rotations)
+                    .with_system(maze_renderer::animate_slice_transitions)
+                    .with_system(completion::track_level_time)
+                    .with_system(completion::count_moves)
+                    .with_system(
+                        completion::level_completed_listener
+                            .label(Completion)
+                            .after(Navigation),
+                    )
+                    .with_system(asset::advance_pack_progress.after(Navigation))
+                    .with_system(input::level_navigation.label(Navigation))
+                    .with_system(input::pause_listener.after(Completion)),
+            )
+            .add_system_set(
+                SystemSet::on_resume(AppState::InMaze).with_system(bindings::discard_actions),
+            )
+            .add_system_set(
+                SystemSet::on_enter(AppState::Paused).with_system(bindings::discard_actions),
+            )
+            .add_system_set(
+                SystemSet::on_enter(AppState::MainMenu).with_system(bindings::discard_actions),
+            )
+            .add_system_set(SystemSet::on_exit(AppState::InMaze).with_system(loader::despawn_level))
+            .add_system_set(
+                SystemSet::on_enter(AppState::Completed)
+                    .with_system(completion::spawn_completion_screen)
+                    .with_system(bindings::discard_actions),
+            )
+            .add_system_set(
+                SystemSet::on_update(AppState::Completed)
+                    .with_system(completion::completion_navigation),
             );
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bevy::{ecs::event::Events, input::InputPlugin};
+
+    fn headless_app() -> App {
+        let mut app = App::new();
+        app.add_plugins(MinimalPlugins)
+            .add_plugin(bevy::asset::AssetPlugin)
+            .add_plugin(InputPlugin)
+            .add_asset::<Mesh>()
+            .add_asset::<StandardMaterial>()
+            .add_state(AppState::MainMenu)
+            .add_plugin(LevelPlugin);
+        app
+    }
+
+    fn load(app: &mut App, level: loader::LoadLevel) {
+        app.world
+            .resource_mut::<Events<loader::LoadLevel>>()
+            .send(level);
+        for _ in 0..5 {
+            app.update();
+        }
+    }
+
+    fn change_state(app: &mut App, change: fn(&mut State<AppState>)) {
+        change(&mut app.world.resource_mut::<State<AppState>>());
+        app.update();
+    }
+
+    #[test]
+    fn only_generated_levels_have_seeds() {
+        let mut app = headless_app();
+        app.update();
+
+        load(
+            &mut app,
+            loader::LoadLevel {
+                layout: loader::LayoutSource::generated(loader::RngSource::Seeded(7)),
+                ..default()
+            },
+        );
+        assert_eq!(
+            app.world.resource::<loader::LevelInfo>().seed,
+            Some((7, crate::maze::GeneratorVersion::LATEST))
+        );
+
+        load(
+            &mut app,
+            loader::LoadLevel {
+                layout: loader::LayoutSource::Stored(Default::default()),
+                ..default()
+            },
+        );
+        assert_eq!(app.world.resource::<loader::LevelInfo>().seed, None);
+    }
+
+    #[test]
+    fn levels_with_one_dimension_arent_loaded() {
+        let mut app = headless_app();
+        app.update();
+        load(
+            &mut app,
+            loader::LoadLevel {
+                dimensions: loader::DimensionLength::Dynamic(vec![5]),
+                ..default()
+            },
+        );
+        assert_eq!(
+            app.world.resource::<State<AppState>>().current(),
+            &AppState::MainMenu
+        );
+    }
+
+    #[test]
+    fn chained_levels_dont_leak_entities() {
+        let mut app = headless_app();
+        app.update();
+        let level = loader::LoadLevel {
+            layout: loader::LayoutSource::generated(loader::RngSource::Seeded(123456789)),
+            dimensions: loader::DimensionLength::Three([4, 4, 4]),
+            ..default()
+        };
+
+        load(&mut app, level.clone());
+        let first = app.world.entities().len();
+        assert_eq!(
+            app.world.resource::<State<AppState>>().current(),
+            &AppState::InMaze
+        );
+
+        for _ in 0..9 {
+            load(&mut app, level.clone());
+            assert_eq!(app.world.entities().len(), first);
+        }
+
+        // Resuming from a pause mustn't spawn the level again.
+        change_state(&mut app, |state| state.push(AppState::Paused).unwrap());
+        change_state(&mut app, |state| state.pop().unwrap());
+        assert_eq!(app.world.entities().len(), first);
+
+        change_state(&mut app, |state| state.replace(AppState::MainMenu).unwrap());
+        let mut levels = app.world.query::<&loader::LevelEntity>();
+        assert_eq!(levels.iter(&app.world).count(), 0);
+    }
+
+    #[test]
+    fn completing_wins_over_pausing_in_the_same_frame() {
+        let mut app = headless_app();
+        app.update();
+        load(
+            &mut app,
+            loader::LoadLevel {
+                layout: loader::LayoutSource::generated(loader::RngSource::Seeded(123456789)),
+                dimensions: loader::DimensionLength::Two([2, 1]),
+                ..default()
+            },
+        );
+
+        let mut actions = app.world.resource_mut::<Events<bindings::ActionPressed>>();
+        actions.send(bindings::ActionPressed(bindings::Action::Back));
+        actions.send(bindings::ActionPressed(bindings::Action::Move(
+            maze_level::Axis::X,
+            maze_level::Direction::Positive,
+        )));
+        app.update();
+
+        let state = app.world.resource::<State<AppState>>();
+        assert_eq!(state.current(), &AppState::Completed);
+        assert_eq!(state.inactives(), &[AppState::InMaze]);
+    }
+}
@@ -1,15 +1,19 @@
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
+// Newer clippy flags bevy 0.7's Bundle derive, which forgets each component after moving it out.
+#![allow(clippy::forget_non_drop)]
 
 mod level;
-mod maze;
+mod menu;
 
 use bevy::prelude::*;
+use nothing_moves::maze;
 
 #[derive(Debug, Clone, Eq, PartialEq, Hash)]
 pub enum AppState {
     MainMenu,
     InMaze,
     Paused,
+    Completed,
 }
 
 fn main() {
@@ -17,11 +21,12 @@ fn main() {
         .add_state(AppState::MainMenu)
         .add_plugins(DefaultPlugins)
         .add_plugin(level::LevelPlugin)
+        .add_plugin(menu::MenuPlugin)
         .add_startup_system(setup)
         .run();
 }
 
-fn setup(mut c: Commands, mut maze_spawner: EventWriter<level::LoadLevel>) {
+fn setup(mut c: Commands, assets: Res<AssetServer>) {
     c.spawn_bundle(OrthographicCameraBundle::new_2d());
     c.spawn_bundle(PointLightBundle {
         point_light: PointLight {
@@ -39,8 +44,8 @@ fn setup(mut c: Commands, mut maze_spawner: EventWriter<level::LoadLevel>) {
         ..Default::default()
     });
     c.spawn_bundle(UiCameraBundle::default());
-    maze_spawner.send(level::LoadLevel {
-        dimensions: level::DimensionLength::Three([4, 15, 2]),
-        ..Default::default()
-    });
+    c.insert_resource(level::LevelPackProgress::restore(
+        assets.load("levels/main.pack.ron"),
+        level::PROGRESS_PATH,
+    ));
 }
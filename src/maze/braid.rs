@@ -0,0 +1,126 @@
+use std::collections::HashSet;
+
+use rand::seq::SliceRandom;
+
+use super::{Grid, MazeGenerator, Passage};
+
+/// Wraps another generator, opening extra passages out of dead ends to form loops.
+///
+/// `density` is the fraction of dead ends removed, 0.0 leaves the maze perfect
+/// and 1.0 removes every dead end that has a wall to open.
+#[derive(Clone, Debug)]
+pub struct Braided<G> {
+    pub generator: G,
+    pub density: f32,
+}
+
+impl<G: MazeGenerator> MazeGenerator for Braided<G> {
+    fn generate(&self, grid: &Grid, rng: &mut impl rand::Rng) -> Vec<Passage> {
+        let mut passages = self.generator.generate(grid, rng);
+        // Leave the rng untouched so unbraided levels match the plain generator.
+        if self.density <= 0.0 {
+            return passages;
+        }
+
+        let mut open: HashSet<_> = passages.iter().copied().collect();
+        let mut degree = vec![0; grid.cell_count()];
+        for passage in passages.iter() {
+            degree[passage.cell] += 1;
+            if let Some(next) = grid.forward(passage.cell, passage.dim) {
+                degree[next] += 1;
+            }
+        }
+
+        let mut dead_ends: Vec<_> = (0..grid.cell_count())
+            .filter(|cell| degree[*cell] == 1)
+            .collect();
+        dead_ends.shuffle(rng);
+
+        for cell in dead_ends {
+            // Opening a neighboring dead end may have already joined this one.
+            if degree[cell] != 1 || rng.gen::<f32>() >= self.density {
+                continue;
+            }
+
+            let closed: Vec<_> = grid
+                .neighbors(cell)
+                .filter(|(_, passage)| !open.contains(passage))
+                .collect();
+            // Prefer joining two dead ends, as that removes both at once.
+            let joining: Vec<_> = closed
+                .iter()
+                .filter(|(next, _)| degree[*next] == 1)
+                .copied()
+                .collect();
+            let candidates = if joining.is_empty() {
+                &closed
+            } else {
+                &joining
+            };
+
+            if let Some((next, passage)) = candidates.choose(rng).copied() {
+                open.insert(passage);
+                passages.push(passage);
+                degree[cell] += 1;
+                degree[next] += 1;
+            }
+        }
+
+        passages
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::maze::*;
+    use rand::prelude::*;
+
+    fn dead_ends(grid: &Grid, passages: &[Passage]) -> usize {
+        let mut degree = vec![0; grid.cell_count()];
+        for passage in passages {
+            degree[passage.cell] += 1;
+            degree[grid.forward(passage.cell, passage.dim).unwrap()] += 1;
+        }
+        degree.iter().filter(|d| **d == 1).count()
+    }
+
+    #[test]
+    fn unbraided_matches_generator() {
+        let grid = Grid::new(&[4, 5, 3]);
+        let plain = Kruskal.generate(&grid, &mut StdRng::seed_from_u64(684153987));
+        let braided = Braided {
+            generator: Kruskal,
+            density: 0.0,
+        }
+        .generate(&grid, &mut StdRng::seed_from_u64(684153987));
+
+        assert_eq!(plain, braided);
+    }
+
+    #[test]
+    fn full_braid_removes_dead_ends() {
+        let mut rng = StdRng::seed_from_u64(684153987);
+        let grid = Grid::new(&[4, 5, 3, 2]);
+        let passages = Braided {
+            generator: RecursiveBacktracker,
+            density: 1.0,
+        }
+        .generate(&grid, &mut rng);
+
+        assert!(passages.len() > 4 * 5 * 3 * 2 - 1);
+        assert_eq!(dead_ends(&grid, &passages), 0);
+    }
+
+    #[test]
+    fn braided_maze_is_solvable() {
+        let mut rng = StdRng::seed_from_u64(684153987);
+        let generator = Braided {
+            generator: Prim,
+            density: 0.5,
+        };
+        let maze = Maze::with_generator(&[4, 4, 4], &generator, &mut rng);
+
+        assert!(maze.solve(&[0, 0, 0], &[3, 3, 3]).is_some());
+    }
+}
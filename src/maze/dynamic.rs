@@ -0,0 +1,194 @@
+use std::collections::VecDeque;
+
+use super::{Coord, Direction, Grid, Kruskal, MazeGenerator, NavigableMaze, Passage, PassageSet};
+
+/// A maze whose number of dimensions is only known at runtime.
+///
+/// Prefer `Maze` when the dimension count is fixed, this pays for an allocation per cell
+/// moved to in exchange for supporting any number of dimensions.
+pub struct DynMaze {
+    grid: Grid,
+    walks: PassageSet,
+    lengths: Box<[Coord]>,
+    wraps: Box<[bool]>,
+}
+
+impl DynMaze {
+    // Generate a maze with the provided number of side lengths.
+    pub fn new(lengths: &[Coord], rng: &mut impl rand::Rng) -> DynMaze {
+        Self::with_generator(lengths, &Kruskal, rng)
+    }
+
+    // Generate a maze with the provided number of side lengths, using the provided algorithm.
+    pub fn with_generator(
+        lengths: &[Coord],
+        generator: &impl MazeGenerator,
+        rng: &mut impl rand::Rng,
+    ) -> DynMaze {
+        Self::with_wrapping(lengths, &[], generator, rng)
+    }
+
+    // Generate a maze where the flagged dimensions wrap around from their last cell to their first.
+    pub fn with_wrapping(
+        lengths: &[Coord],
+        wraps: &[bool],
+        generator: &impl MazeGenerator,
+        rng: &mut impl rand::Rng,
+    ) -> DynMaze {
+        let grid = Grid::wrapping(lengths, wraps);
+        let mut walks = PassageSet::new(grid.cell_count(), grid.dims());
+        walks.extend(generator.generate(&grid, rng));
+        let wraps = (0..grid.dims()).map(|dim| grid.wraps(dim)).collect();
+
+        DynMaze {
+            grid,
+            walks,
+            lengths: lengths.into(),
+            wraps,
+        }
+    }
+
+    fn cell(&self, index: usize) -> Box<[Coord]> {
+        let mut coords = self.origin();
+        self.grid.write_coords(index, &mut coords);
+        coords
+    }
+
+    fn neighbor_index(&self, point: &[Coord], dimension: usize, dir: Direction) -> Option<usize> {
+        if dimension >= self.grid.dims() {
+            return None;
+        }
+        let index = self.grid.index_of(point)?;
+        self.grid.neighbor(index, dimension, dir)
+    }
+
+    fn is_open(&self, index: usize, dimension: usize, dir: Direction) -> bool {
+        match dir {
+            Direction::Positive => self.walks.contains(Passage {
+                cell: index,
+                dim: dimension,
+            }),
+            Direction::Negative => self
+                .grid
+                .backward(index, dimension)
+                .map(|prev| {
+                    self.walks.contains(Passage {
+                        cell: prev,
+                        dim: dimension,
+                    })
+                })
+                .unwrap_or(false),
+        }
+    }
+}
+
+impl NavigableMaze for DynMaze {
+    type Cell = Box<[Coord]>;
+
+    #[inline]
+    fn lengths(&self) -> &[Coord] {
+        &self.lengths
+    }
+
+    #[inline]
+    fn wraps(&self) -> &[bool] {
+        &self.wraps
+    }
+
+    #[inline]
+    fn grid(&self) -> &Grid {
+        &self.grid
+    }
+
+    fn origin(&self) -> Self::Cell {
+        vec![0; self.grid.dims()].into()
+    }
+
+    fn can_move(&self, point: &Self::Cell, dimension: usize) -> Option<bool> {
+        self.neighbor_index(point, dimension, Direction::Positive)?;
+        let index = self.grid.index_of(point)?;
+        Some(self.is_open(index, dimension, Direction::Positive))
+    }
+
+    fn neighbor(&self, point: &Self::Cell, dimension: usize, dir: Direction) -> Option<Self::Cell> {
+        self.neighbor_index(point, dimension, dir)
+            .map(|index| self.cell(index))
+    }
+
+    fn step(&self, point: &Self::Cell, dimension: usize, dir: Direction) -> Option<Self::Cell> {
+        let next = self.neighbor_index(point, dimension, dir)?;
+        let index = self.grid.index_of(point)?;
+        if self.is_open(index, dimension, dir) {
+            Some(self.cell(next))
+        } else {
+            None
+        }
+    }
+
+    fn farthest_from(&self, start: &Self::Cell) -> Option<Self::Cell> {
+        let start = self.grid.index_of(start)?;
+        let mut visited = vec![false; self.grid.cell_count()];
+        let mut pending = VecDeque::new();
+        let mut last = start;
+        visited[start] = true;
+        pending.push_back(start);
+
+        while let Some(index) = pending.pop_front() {
+            last = index;
+            for (next, passage) in self.grid.neighbors(index) {
+                if !visited[next] && self.walks.contains(passage) {
+                    visited[next] = true;
+                    pending.push_back(next);
+                }
+            }
+        }
+
+        Some(self.cell(last))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::maze::*;
+    use rand::prelude::*;
+
+    #[test]
+    fn matches_fixed_maze() {
+        let lengths = [3, 4, 2, 3];
+        let fixed = Maze::new(&lengths, &mut StdRng::seed_from_u64(684153987));
+        let dynamic = DynMaze::new(&lengths, &mut StdRng::seed_from_u64(684153987));
+
+        let grid = Grid::new(&lengths);
+        let mut coords = [0; 4];
+        for index in 0..grid.cell_count() {
+            grid.write_coords(index, &mut coords);
+            let cell: Box<[Coord]> = coords.into();
+            for dim in 0..4 {
+                assert_eq!(
+                    NavigableMaze::can_move(&dynamic, &cell, dim),
+                    fixed.can_move(&coords, dim)
+                );
+                for dir in [Direction::Positive, Direction::Negative] {
+                    assert_eq!(
+                        NavigableMaze::step(&dynamic, &cell, dim, dir).as_deref(),
+                        fixed.step(&coords, dim, dir).as_ref().map(|c| &c[..])
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn supports_many_dimensions() {
+        let mut rng = StdRng::seed_from_u64(684153987);
+        let lengths = [2; 12];
+        let maze = DynMaze::new(&lengths, &mut rng);
+
+        let origin = maze.origin();
+        assert_eq!(origin.len(), 12);
+        assert_eq!(NavigableMaze::can_move(&maze, &origin, 12), None);
+        assert_eq!(NavigableMaze::can_move(&maze, &vec![1; 12].into(), 3), None);
+        assert!(maze.farthest_from(&origin).is_some());
+    }
+}
@@ -0,0 +1,458 @@
+use serde::{Deserialize, Serialize};
+
+use super::{Coord, Grid, Maze, MazeGenerator, Passage, PassageSet};
+
+/// The version written by this build. Files from `OLDEST_VERSION` up to this one can be read.
+pub const FORMAT_VERSION: u32 = 2;
+
+/// The oldest version that can still be read, which stored each coordinate in a single byte.
+const OLDEST_VERSION: u32 = 1;
+
+const MAGIC: &[u8; 4] = b"NMAZ";
+
+#[derive(Debug, thiserror::Error)]
+pub enum FormatError {
+    #[error("not a maze file")]
+    BadMagic,
+    #[error("unsupported maze format version {0}, expected {OLDEST_VERSION} to {FORMAT_VERSION}")]
+    UnsupportedVersion(u32),
+    #[error("maze data ended early")]
+    Truncated,
+    #[error("unexpected data after the end of the maze")]
+    TrailingData,
+    #[error("expected a maze with {expected} dimensions, found {found}")]
+    DimensionMismatch { expected: usize, found: usize },
+    #[error("invalid maze: {0}")]
+    Invalid(&'static str),
+    #[error(transparent)]
+    Ron(#[from] ron::Error),
+}
+
+/// A passage in a `MazeRecord`, leading from `cell` one step along `dim` in the positive direction.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct PassageRecord {
+    pub cell: Vec<Coord>,
+    pub dim: usize,
+}
+
+/// A generated maze along with where the player starts and needs to reach,
+/// stored independently of the generator and rng that produced it.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct MazeRecord {
+    pub version: u32,
+    pub lengths: Vec<Coord>,
+    /// Which dimensions wrap around, may be left empty when none do.
+    #[serde(default)]
+    pub wraps: Vec<bool>,
+    pub start: Vec<Coord>,
+    pub goal: Vec<Coord>,
+    pub passages: Vec<PassageRecord>,
+}
+
+/// A maze read back from a `MazeRecord`.
+#[derive(Debug, PartialEq, Eq)]
+pub struct StoredMaze<const DIMS: usize> {
+    pub maze: Maze<DIMS>,
+    pub start: [Coord; DIMS],
+    pub goal: [Coord; DIMS],
+}
+
+impl MazeRecord {
+    pub fn new<const DIMS: usize>(
+        maze: &Maze<DIMS>,
+        start: &[Coord; DIMS],
+        goal: &[Coord; DIMS],
+    ) -> Self {
+        Self {
+            version: FORMAT_VERSION,
+            lengths: maze.lengths().to_vec(),
+            wraps: maze.wraps().to_vec(),
+            start: start.to_vec(),
+            goal: goal.to_vec(),
+            passages: maze
+                .passages()
+                .map(|(cell, dim)| PassageRecord {
+                    cell: cell.to_vec(),
+                    dim,
+                })
+                .collect(),
+        }
+    }
+
+    /// The number of dimensions of the stored maze.
+    pub fn dims(&self) -> usize {
+        self.lengths.len()
+    }
+
+    /// Checks the record describes a maze that can be navigated, returning the grid it spans.
+    fn validate(&self) -> Result<Grid, FormatError> {
+        if !(OLDEST_VERSION..=FORMAT_VERSION).contains(&self.version) {
+            return Err(FormatError::UnsupportedVersion(self.version));
+        }
+        let dims = self.dims();
+        if dims == 0 {
+            return Err(FormatError::Invalid("a maze needs at least one dimension"));
+        }
+        if self.lengths.contains(&0) {
+            return Err(FormatError::Invalid(
+                "every dimension needs at least one cell",
+            ));
+        }
+        if !self.wraps.is_empty() && self.wraps.len() != dims {
+            return Err(FormatError::Invalid("wraps doesn't match the dimensions"));
+        }
+        if Grid::checked_cell_count(&self.lengths).is_none() {
+            return Err(FormatError::Invalid("the maze has too many cells"));
+        }
+
+        let grid = Grid::wrapping(&self.lengths, &self.wraps);
+        for point in [&self.start, &self.goal] {
+            if point.len() != dims || grid.index_of(point).is_none() {
+                return Err(FormatError::Invalid("start or goal is outside of the maze"));
+            }
+        }
+
+        validate_passages(&grid, &self.passages)?;
+
+        Ok(grid)
+    }
+
+    fn passage_set(&self, grid: &Grid) -> PassageSet {
+        let mut walks = PassageSet::new(grid.cell_count(), grid.dims());
+        walks.extend(passage_indices(grid, &self.passages));
+        walks
+    }
+
+    /// Rebuilds the maze, failing if the record is invalid or has a different number of dimensions.
+    pub fn to_maze<const DIMS: usize>(&self) -> Result<StoredMaze<DIMS>, FormatError> {
+        let grid = self.validate()?;
+        let dimension_mismatch = || FormatError::DimensionMismatch {
+            expected: DIMS,
+            found: self.dims(),
+        };
+
+        let mut wraps = [false; DIMS];
+        for (dim, wrap) in wraps.iter_mut().enumerate() {
+            *wrap = grid.wraps(dim);
+        }
+
+        Ok(StoredMaze {
+            maze: Maze {
+                walks: self.passage_set(&grid),
+                lengths: self.lengths[..]
+                    .try_into()
+                    .map_err(|_| dimension_mismatch())?,
+                wraps,
+                grid,
+            },
+            start: self.start[..]
+                .try_into()
+                .map_err(|_| dimension_mismatch())?,
+            goal: self.goal[..].try_into().map_err(|_| dimension_mismatch())?,
+        })
+    }
+
+    /// The human readable form, intended for hand edited levels.
+    pub fn to_ron(&self) -> Result<String, FormatError> {
+        self.validate()?;
+        Ok(ron::ser::to_string_pretty(
+            self,
+            ron::ser::PrettyConfig::default(),
+        )?)
+    }
+
+    pub fn from_ron(s: &str) -> Result<Self, FormatError> {
+        let record: Self = ron::from_str(s)?;
+        record.validate()?;
+        Ok(record)
+    }
+
+    /// The compact binary form, always written in the current version.
+    ///
+    /// Lays out the magic, version, dimension count, then the lengths, wraps, start and goal
+    /// with two little endian bytes per coordinate, followed by one bit per cell and dimension
+    /// flagging each open passage.
+    pub fn to_bytes(&self) -> Result<Vec<u8>, FormatError> {
+        let grid = self.validate()?;
+        let dims = self.dims();
+        if dims > u8::MAX as usize {
+            return Err(FormatError::Invalid(
+                "too many dimensions for the binary form",
+            ));
+        }
+
+        let mut bytes = MAGIC.to_vec();
+        bytes.extend(FORMAT_VERSION.to_le_bytes());
+        bytes.push(dims as u8);
+        bytes.extend(self.lengths.iter().flat_map(|l| l.to_le_bytes()));
+        bytes.extend((0..dims).map(|dim| grid.wraps(dim) as u8));
+        bytes.extend(self.start.iter().flat_map(|c| c.to_le_bytes()));
+        bytes.extend(self.goal.iter().flat_map(|c| c.to_le_bytes()));
+
+        let mut bits = vec![0u8; (grid.cell_count() * dims).div_ceil(8)];
+        for passage in self.passage_set(&grid).iter() {
+            let bit = passage.cell * dims + passage.dim;
+            bits[bit / 8] |= 1 << (bit % 8);
+        }
+        bytes.extend(bits);
+
+        Ok(bytes)
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, FormatError> {
+        let mut reader = Reader(bytes);
+        if reader.take(MAGIC.len())? != MAGIC {
+            return Err(FormatError::BadMagic);
+        }
+        let version = u32::from_le_bytes(reader.take(4)?.try_into().unwrap());
+        if !(OLDEST_VERSION..=FORMAT_VERSION).contains(&version) {
+            return Err(FormatError::UnsupportedVersion(version));
+        }
+
+        let dims = reader.take(1)?[0] as usize;
+        let lengths = reader.take_coords(dims, version)?;
+        let wraps = reader.take(dims)?.iter().map(|w| *w != 0).collect();
+        let start = reader.take_coords(dims, version)?;
+        let goal = reader.take_coords(dims, version)?;
+
+        let cell_count = Grid::checked_cell_count(&lengths)
+            .ok_or(FormatError::Invalid("the maze has too many cells"))?;
+        let bits = reader.take((cell_count * dims).div_ceil(8))?;
+        if !reader.0.is_empty() {
+            return Err(FormatError::TrailingData);
+        }
+
+        let grid = Grid::new(&lengths);
+        let mut passages = Vec::new();
+        for bit in (0..cell_count * dims).filter(|bit| bits[bit / 8] & (1 << (bit % 8)) != 0) {
+            let mut cell = vec![0; dims];
+            grid.write_coords(bit / dims, &mut cell);
+            passages.push(PassageRecord {
+                cell,
+                dim: bit % dims,
+            });
+        }
+
+        // Older versions are read into the current layout, so the record is upgraded.
+        let record = Self {
+            version: FORMAT_VERSION,
+            lengths,
+            wraps,
+            start,
+            goal,
+            passages,
+        };
+        record.validate()?;
+        Ok(record)
+    }
+}
+
+/// Checks every passage leads between two cells of the grid.
+pub fn validate_passages(grid: &Grid, passages: &[PassageRecord]) -> Result<(), FormatError> {
+    for passage in passages {
+        if passage.cell.len() != grid.dims() || passage.dim >= grid.dims() {
+            return Err(FormatError::Invalid("passage doesn't match the dimensions"));
+        }
+        grid.index_of(&passage.cell)
+            .and_then(|cell| grid.forward(cell, passage.dim))
+            .ok_or(FormatError::Invalid("passage leads outside of the maze"))?;
+    }
+    Ok(())
+}
+
+fn passage_indices<'a>(
+    grid: &'a Grid,
+    passages: &'a [PassageRecord],
+) -> impl Iterator<Item = Passage> + 'a {
+    passages.iter().filter_map(|passage| {
+        let cell = grid.index_of(&passage.cell)?;
+        grid.forward(cell, passage.dim)?;
+        Some(Passage {
+            cell,
+            dim: passage.dim,
+        })
+    })
+}
+
+/// Produces a stored set of passages instead of generating them, ignoring the rng.
+///
+/// Passages that don't fit the grid are skipped, use `validate_passages` to reject them instead.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct FixedPassages(pub Vec<PassageRecord>);
+
+impl MazeGenerator for FixedPassages {
+    fn generate(&self, grid: &Grid, _rng: &mut impl rand::Rng) -> Vec<Passage> {
+        passage_indices(grid, &self.0).collect()
+    }
+}
+
+struct Reader<'a>(&'a [u8]);
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, count: usize) -> Result<&'a [u8], FormatError> {
+        if self.0.len() < count {
+            return Err(FormatError::Truncated);
+        }
+        let (taken, rest) = self.0.split_at(count);
+        self.0 = rest;
+        Ok(taken)
+    }
+
+    /// Reads `count` coordinates, one byte each before version 2 and two bytes each since.
+    fn take_coords(&mut self, count: usize, version: u32) -> Result<Vec<Coord>, FormatError> {
+        Ok(if version < 2 {
+            self.take(count)?.iter().map(|c| *c as Coord).collect()
+        } else {
+            self.take(count * 2)?
+                .chunks_exact(2)
+                .map(|c| Coord::from_le_bytes([c[0], c[1]]))
+                .collect()
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::maze::*;
+    use rand::prelude::*;
+
+    fn record() -> MazeRecord {
+        let mut rng = StdRng::seed_from_u64(684153987);
+        let maze = Maze::with_wrapping(&[4, 5, 3], &[false, true, false], &Kruskal, &mut rng);
+        MazeRecord::new(&maze, &[0, 0, 0], &[3, 2, 2])
+    }
+
+    #[test]
+    fn round_trips_maze() {
+        let mut rng = StdRng::seed_from_u64(684153987);
+        let maze = Maze::with_wrapping(&[4, 5, 3], &[false, true, false], &Prim, &mut rng);
+        let record = MazeRecord::new(&maze, &[1, 0, 0], &[3, 4, 2]);
+
+        let stored = record.to_maze::<3>().unwrap();
+        assert_eq!(stored.maze, maze);
+        assert_eq!(stored.start, [1, 0, 0]);
+        assert_eq!(stored.goal, [3, 4, 2]);
+    }
+
+    #[test]
+    fn round_trips_ron() {
+        let record = record();
+        let text = record.to_ron().unwrap();
+        assert_eq!(MazeRecord::from_ron(&text).unwrap(), record);
+    }
+
+    #[test]
+    fn round_trips_bytes() {
+        let record = record();
+        let bytes = record.to_bytes().unwrap();
+        assert_eq!(bytes.len(), 4 + 4 + 1 + 3 * 7 + (60 * 3usize).div_ceil(8));
+        assert_eq!(MazeRecord::from_bytes(&bytes).unwrap(), record);
+    }
+
+    #[test]
+    fn reads_single_byte_coordinates_from_version_1() {
+        let record = record();
+        let mut bytes = MAGIC.to_vec();
+        bytes.extend(1u32.to_le_bytes());
+        bytes.push(3);
+        bytes.extend([4, 5, 3, 0, 1, 0, 0, 0, 0, 3, 2, 2]);
+        bytes.extend(&record.to_bytes().unwrap()[4 + 4 + 1 + 3 * 7..]);
+
+        assert_eq!(MazeRecord::from_bytes(&bytes).unwrap(), record);
+    }
+
+    #[test]
+    fn round_trips_long_dimensions() {
+        let mut rng = StdRng::seed_from_u64(684153987);
+        let maze = Maze::new(&[1000, 3, 1], &mut rng);
+        let record = MazeRecord::new(&maze, &[999, 0, 0], &[0, 2, 0]);
+
+        let bytes = record.to_bytes().unwrap();
+        let stored = MazeRecord::from_bytes(&bytes)
+            .unwrap()
+            .to_maze::<3>()
+            .unwrap();
+        assert_eq!(stored.maze, maze);
+        assert_eq!(stored.start, [999, 0, 0]);
+    }
+
+    #[test]
+    fn fixed_passages_rebuild_maze() {
+        let mut rng = StdRng::seed_from_u64(684153987);
+        let maze = Maze::new(&[4, 5, 3], &mut rng);
+        let record = MazeRecord::new(&maze, &[0, 0, 0], &[3, 4, 2]);
+
+        let fixed = Maze::with_generator(&[4, 5, 3], &FixedPassages(record.passages), &mut rng);
+        assert_eq!(fixed, maze);
+    }
+
+    #[test]
+    fn rejects_other_versions() {
+        let mut record = record();
+        record.version = FORMAT_VERSION + 1;
+        assert!(matches!(
+            record.to_bytes(),
+            Err(FormatError::UnsupportedVersion(_))
+        ));
+
+        let mut bytes = self::record().to_bytes().unwrap();
+        bytes[4] += 1;
+        assert!(matches!(
+            MazeRecord::from_bytes(&bytes),
+            Err(FormatError::UnsupportedVersion(_))
+        ));
+    }
+
+    #[test]
+    fn rejects_malformed_data() {
+        let bytes = record().to_bytes().unwrap();
+        assert!(matches!(
+            MazeRecord::from_bytes(&bytes[..bytes.len() - 1]),
+            Err(FormatError::Truncated)
+        ));
+        assert!(matches!(
+            MazeRecord::from_bytes(&[bytes.as_slice(), &[0]].concat()),
+            Err(FormatError::TrailingData)
+        ));
+        assert!(matches!(
+            MazeRecord::from_bytes(b"nope"),
+            Err(FormatError::BadMagic)
+        ));
+
+        let mut record = record();
+        record.passages.push(PassageRecord {
+            cell: vec![3, 0, 0],
+            dim: 0,
+        });
+        assert!(matches!(record.to_ron(), Err(FormatError::Invalid(_))));
+        assert!(matches!(
+            self::record().to_maze::<2>(),
+            Err(FormatError::DimensionMismatch {
+                expected: 2,
+                found: 3
+            })
+        ));
+    }
+
+    #[test]
+    fn rejects_oversized_mazes() {
+        let dims = u8::MAX as usize;
+        let mut bytes = MAGIC.to_vec();
+        bytes.extend(FORMAT_VERSION.to_le_bytes());
+        bytes.push(dims as u8);
+        bytes.extend(vec![0xff; dims * 2]);
+        bytes.extend(vec![0; dims * 5]);
+        assert!(matches!(
+            MazeRecord::from_bytes(&bytes),
+            Err(FormatError::Invalid(_))
+        ));
+
+        let mut record = record();
+        record.lengths = vec![Coord::MAX; 8];
+        record.start = vec![0; 8];
+        record.goal = vec![0; 8];
+        record.passages.clear();
+        assert!(matches!(record.to_ron(), Err(FormatError::Invalid(_))));
+    }
+}
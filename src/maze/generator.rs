@@ -0,0 +1,589 @@
+use std::collections::BinaryHeap;
+
+use serde::{Deserialize, Serialize};
+
+use super::{Coord, Direction};
+
+/// An open passage between `cell` and the cell one step along `dim` in the positive direction.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Passage {
+    pub cell: usize,
+    pub dim: usize,
+}
+
+/// Produces the passages of a maze, usually a perfect maze (a spanning tree over every cell).
+pub trait MazeGenerator {
+    fn generate(&self, grid: &Grid, rng: &mut impl rand::Rng) -> Vec<Passage>;
+}
+
+/// The generation algorithms a level can be built with.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub enum Generator {
+    Kruskal(Kruskal),
+    RecursiveBacktracker(RecursiveBacktracker),
+    Prim(Prim),
+    Wilson(Wilson),
+    AldousBroder(AldousBroder),
+    GrowingTree(GrowingTree),
+}
+
+impl Default for Generator {
+    fn default() -> Self {
+        Generator::Kruskal(Kruskal)
+    }
+}
+
+impl MazeGenerator for Generator {
+    fn generate(&self, grid: &Grid, rng: &mut impl rand::Rng) -> Vec<Passage> {
+        match self {
+            Generator::Kruskal(g) => g.generate(grid, rng),
+            Generator::RecursiveBacktracker(g) => g.generate(grid, rng),
+            Generator::Prim(g) => g.generate(grid, rng),
+            Generator::Wilson(g) => g.generate(grid, rng),
+            Generator::AldousBroder(g) => g.generate(grid, rng),
+            Generator::GrowingTree(g) => g.generate(grid, rng),
+        }
+    }
+}
+
+/// Index math over the linearized cells of a maze with the provided side lengths.
+///
+/// Cells are addressed by their linear index, where the first dimension is the lowest power.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Grid {
+    lengths: Vec<usize>,
+    strides: Vec<usize>,
+    wraps: Vec<bool>,
+    cell_count: usize,
+}
+
+impl Grid {
+    pub fn new(lengths: &[Coord]) -> Self {
+        Self::wrapping(lengths, &[])
+    }
+
+    /// A grid where stepping off the end of a flagged dimension lands back at the start.
+    /// Dimensions shorter than three never wrap, as both ends would already be neighbors.
+    pub fn wrapping(lengths: &[Coord], wraps: &[bool]) -> Self {
+        let cell_count = Self::checked_cell_count(lengths).expect("maze has too many cells");
+        let mut strides = Vec::with_capacity(lengths.len());
+        let mut stride = 1;
+        for length in lengths {
+            strides.push(stride);
+            stride *= *length as usize;
+        }
+        Self {
+            lengths: lengths.iter().map(|l| *l as usize).collect(),
+            strides,
+            wraps: lengths
+                .iter()
+                .enumerate()
+                .map(|(dim, length)| *length > 2 && wraps.get(dim).copied().unwrap_or(false))
+                .collect(),
+            cell_count,
+        }
+    }
+
+    /// The number of cells a grid with these lengths would have, or `None` if the cells or
+    /// their passages (one per cell and dimension) can't be counted in a `usize`.
+    pub fn checked_cell_count(lengths: &[Coord]) -> Option<usize> {
+        let cell_count = lengths
+            .iter()
+            .try_fold(1usize, |count, length| count.checked_mul(*length as usize))?;
+        cell_count.checked_mul(lengths.len())?;
+        Some(cell_count)
+    }
+
+    #[inline]
+    pub fn cell_count(&self) -> usize {
+        self.cell_count
+    }
+
+    #[inline]
+    pub fn dims(&self) -> usize {
+        self.lengths.len()
+    }
+
+    /// Whether stepping off the end of `dim` lands back at the start.
+    #[inline]
+    pub fn wraps(&self, dim: usize) -> bool {
+        self.wraps[dim]
+    }
+
+    /// The linear index of a cell, if the coordinates are inside the maze.
+    pub fn index_of(&self, coords: &[Coord]) -> Option<usize> {
+        if coords.len() != self.dims() {
+            return None;
+        }
+        let mut index = 0;
+        for ((coord, length), stride) in coords.iter().zip(&self.lengths).zip(&self.strides) {
+            if *coord as usize >= *length {
+                return None;
+            }
+            index += *coord as usize * stride;
+        }
+        Some(index)
+    }
+
+    /// Writes the coordinates of the cell at a linear index into `coords`.
+    pub fn write_coords(&self, cell: usize, coords: &mut [Coord]) {
+        for ((coord, length), stride) in coords.iter_mut().zip(&self.lengths).zip(&self.strides) {
+            *coord = ((cell / stride) % length) as Coord;
+        }
+    }
+
+    /// The cell one step along `dim` in the positive direction, if it is inside the maze.
+    pub fn forward(&self, cell: usize, dim: usize) -> Option<usize> {
+        let coord = (cell / self.strides[dim]) % self.lengths[dim];
+        if coord + 1 < self.lengths[dim] {
+            Some(cell + self.strides[dim])
+        } else if self.wraps[dim] {
+            Some(cell - coord * self.strides[dim])
+        } else {
+            None
+        }
+    }
+
+    /// The cell one step along `dim` in the negative direction, if it is inside the maze.
+    pub fn backward(&self, cell: usize, dim: usize) -> Option<usize> {
+        let coord = (cell / self.strides[dim]) % self.lengths[dim];
+        if coord > 0 {
+            Some(cell - self.strides[dim])
+        } else if self.wraps[dim] {
+            Some(cell + (self.lengths[dim] - 1) * self.strides[dim])
+        } else {
+            None
+        }
+    }
+
+    /// The cell one step along `dim` in `dir`, ignoring walls. This is where the edges of the
+    /// maze are decided:
+    /// - the ends of a dimension that doesn't wrap have no neighbor past them,
+    /// - the ends of a wrapped dimension are neighbors of each other,
+    /// - dimensions of length one have no neighbors along them.
+    pub fn neighbor(&self, cell: usize, dim: usize, dir: Direction) -> Option<usize> {
+        match dir {
+            Direction::Positive => self.forward(cell, dim),
+            Direction::Negative => self.backward(cell, dim),
+        }
+    }
+
+    /// Every neighbor of a cell, paired with the passage that would join them.
+    pub fn neighbors(&self, cell: usize) -> impl Iterator<Item = (usize, Passage)> + '_ {
+        (0..self.dims()).flat_map(move |dim| {
+            [Direction::Positive, Direction::Negative]
+                .into_iter()
+                .filter_map(move |dir| {
+                    let next = self.neighbor(cell, dim, dir)?;
+                    // Passages are stored on the cell at their negative end.
+                    let passage = match dir {
+                        Direction::Positive => Passage { cell, dim },
+                        Direction::Negative => Passage { cell: next, dim },
+                    };
+                    Some((next, passage))
+                })
+        })
+    }
+
+    fn random_neighbor(&self, cell: usize, rng: &mut impl rand::Rng) -> Option<(usize, Passage)> {
+        let neighbors: Vec<_> = self.neighbors(cell).collect();
+        if neighbors.is_empty() {
+            None
+        } else {
+            Some(neighbors[random_index(rng, neighbors.len())])
+        }
+    }
+}
+
+/// A random index below `len`, sampled as a `u64` so 32 and 64 bit targets pick the same index.
+fn random_index(rng: &mut impl rand::Rng, len: usize) -> usize {
+    rng.gen_range(0..len as u64) as usize
+}
+
+/// Randomized Kruskal, merging cells in order of a random weight assigned to every edge.
+#[derive(Clone, Copy, Debug, Default, Serialize, Deserialize)]
+pub struct Kruskal;
+
+impl MazeGenerator for Kruskal {
+    fn generate(&self, grid: &Grid, rng: &mut impl rand::Rng) -> Vec<Passage> {
+        let cell_count = grid.cell_count();
+        let mut trees = DisjointSet::new(cell_count);
+
+        let mut pending_edges = BinaryHeap::with_capacity(cell_count * grid.dims());
+        for index in 0..cell_count {
+            for dim in 0..grid.dims() {
+                // Every pair draws a weight, even past the end of a dimension,
+                // so a seed keeps producing the same maze.
+                let weight = rng.next_u32();
+                if let Some(next) = grid.forward(index, dim) {
+                    pending_edges.push((weight, index, dim, next));
+                }
+            }
+        }
+
+        // In general, each cell will be linked with at most one other, but this will be less.
+        let mut passages = Vec::with_capacity(cell_count);
+
+        while let Some((_, a, dim, b)) = pending_edges.pop() {
+            if trees.try_merge(a, b) {
+                passages.push(Passage { cell: a, dim });
+            }
+        }
+
+        passages
+    }
+}
+
+/// Depth first search, carving to a random unvisited neighbor and backtracking at dead ends.
+///
+/// Produces long winding corridors with few branches.
+#[derive(Clone, Copy, Debug, Default, Serialize, Deserialize)]
+pub struct RecursiveBacktracker;
+
+impl MazeGenerator for RecursiveBacktracker {
+    fn generate(&self, grid: &Grid, rng: &mut impl rand::Rng) -> Vec<Passage> {
+        GrowingTree { newest_bias: 1.0 }.generate(grid, rng)
+    }
+}
+
+/// Randomized Prim, growing the maze from a random frontier edge each step.
+///
+/// Produces many short dead ends radiating from the start.
+#[derive(Clone, Copy, Debug, Default, Serialize, Deserialize)]
+pub struct Prim;
+
+impl MazeGenerator for Prim {
+    fn generate(&self, grid: &Grid, rng: &mut impl rand::Rng) -> Vec<Passage> {
+        if grid.cell_count() == 0 {
+            return Vec::new();
+        }
+
+        let mut visited = vec![false; grid.cell_count()];
+        let mut passages = Vec::with_capacity(grid.cell_count());
+        let mut frontier = Vec::new();
+
+        let start = random_index(rng, grid.cell_count());
+        visited[start] = true;
+        frontier.extend(grid.neighbors(start));
+
+        while !frontier.is_empty() {
+            let (cell, passage) = frontier.swap_remove(random_index(rng, frontier.len()));
+            if visited[cell] {
+                continue;
+            }
+            visited[cell] = true;
+            passages.push(passage);
+            frontier.extend(grid.neighbors(cell).filter(|(next, _)| !visited[*next]));
+        }
+
+        passages
+    }
+}
+
+/// Wilson's algorithm, joining loop-erased random walks onto the maze.
+///
+/// Produces an unbiased sample of all possible mazes.
+#[derive(Clone, Copy, Debug, Default, Serialize, Deserialize)]
+pub struct Wilson;
+
+impl MazeGenerator for Wilson {
+    fn generate(&self, grid: &Grid, rng: &mut impl rand::Rng) -> Vec<Passage> {
+        if grid.cell_count() == 0 {
+            return Vec::new();
+        }
+
+        let mut in_maze = vec![false; grid.cell_count()];
+        // The step last taken out of each cell during the current walk, overwriting erases loops.
+        let mut exits: Vec<Option<(usize, Passage)>> = vec![None; grid.cell_count()];
+        let mut passages = Vec::with_capacity(grid.cell_count());
+
+        in_maze[random_index(rng, grid.cell_count())] = true;
+
+        for start in 0..grid.cell_count() {
+            let mut cursor = start;
+            while !in_maze[cursor] {
+                let step = grid.random_neighbor(cursor, rng).unwrap();
+                exits[cursor] = Some(step);
+                cursor = step.0;
+            }
+
+            let mut cursor = start;
+            while !in_maze[cursor] {
+                let (next, passage) = exits[cursor].unwrap();
+                in_maze[cursor] = true;
+                passages.push(passage);
+                cursor = next;
+            }
+        }
+
+        passages
+    }
+}
+
+/// Aldous-Broder, a random walk that carves whenever it enters an unvisited cell.
+///
+/// Produces an unbiased sample of all possible mazes, but is slow to finish on large mazes.
+#[derive(Clone, Copy, Debug, Default, Serialize, Deserialize)]
+pub struct AldousBroder;
+
+impl MazeGenerator for AldousBroder {
+    fn generate(&self, grid: &Grid, rng: &mut impl rand::Rng) -> Vec<Passage> {
+        if grid.cell_count() == 0 {
+            return Vec::new();
+        }
+
+        let mut visited = vec![false; grid.cell_count()];
+        let mut passages = Vec::with_capacity(grid.cell_count());
+
+        let mut cursor = random_index(rng, grid.cell_count());
+        visited[cursor] = true;
+        let mut remaining = grid.cell_count() - 1;
+
+        while remaining > 0 {
+            let (next, passage) = grid.random_neighbor(cursor, rng).unwrap();
+            if !visited[next] {
+                visited[next] = true;
+                passages.push(passage);
+                remaining -= 1;
+            }
+            cursor = next;
+        }
+
+        passages
+    }
+}
+
+/// Growing tree, extending from either the newest or a random active cell.
+///
+/// `newest_bias` is the chance of picking the newest cell; 1.0 behaves like the recursive
+/// backtracker while 0.0 behaves much like Prim.
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct GrowingTree {
+    pub newest_bias: f32,
+}
+
+impl Default for GrowingTree {
+    fn default() -> Self {
+        Self { newest_bias: 0.5 }
+    }
+}
+
+impl MazeGenerator for GrowingTree {
+    fn generate(&self, grid: &Grid, rng: &mut impl rand::Rng) -> Vec<Passage> {
+        if grid.cell_count() == 0 {
+            return Vec::new();
+        }
+
+        let mut visited = vec![false; grid.cell_count()];
+        let mut passages = Vec::with_capacity(grid.cell_count());
+        let mut active = Vec::new();
+
+        let start = random_index(rng, grid.cell_count());
+        visited[start] = true;
+        active.push(start);
+
+        while !active.is_empty() {
+            let index = if rng.gen::<f32>() < self.newest_bias {
+                active.len() - 1
+            } else {
+                random_index(rng, active.len())
+            };
+            let cell = active[index];
+
+            let unvisited: Vec<_> = grid
+                .neighbors(cell)
+                .filter(|(next, _)| !visited[*next])
+                .collect();
+            if unvisited.is_empty() {
+                active.remove(index);
+                continue;
+            }
+
+            let (next, passage) = unvisited[random_index(rng, unvisited.len())];
+            visited[next] = true;
+            passages.push(passage);
+            active.push(next);
+        }
+
+        passages
+    }
+}
+
+/// Disjoint sets over the linear cell index, tracking which cells are already joined.
+struct DisjointSet {
+    parents: Vec<usize>,
+    ranks: Vec<u8>,
+}
+
+impl DisjointSet {
+    fn new(cell_count: usize) -> Self {
+        Self {
+            parents: (0..cell_count).collect(),
+            ranks: vec![0; cell_count],
+        }
+    }
+
+    /// Gets the root of the particular cell tree, pointing every cell on the way directly at it.
+    fn get_root(&mut self, cell: usize) -> usize {
+        let mut root = cell;
+        while self.parents[root] != root {
+            root = self.parents[root];
+        }
+
+        let mut current = cell;
+        while self.parents[current] != root {
+            current = std::mem::replace(&mut self.parents[current], root);
+        }
+
+        root
+    }
+
+    /// Attempts to merge both cells, returning true if they were different trees previously.
+    fn try_merge(&mut self, a: usize, b: usize) -> bool {
+        let ra = self.get_root(a);
+        let rb = self.get_root(b);
+        if ra == rb {
+            return false;
+        }
+
+        // Hang the shallower tree under the deeper one, so trees stay logarithmic in depth.
+        match self.ranks[ra].cmp(&self.ranks[rb]) {
+            std::cmp::Ordering::Less => self.parents[ra] = rb,
+            std::cmp::Ordering::Greater => self.parents[rb] = ra,
+            std::cmp::Ordering::Equal => {
+                self.parents[rb] = ra;
+                self.ranks[ra] += 1;
+            }
+        }
+        true
+    }
+}
+
+/// One of every perfect generator, for tests that should hold for all of them.
+#[cfg(test)]
+pub(crate) fn all_generators() -> Vec<Generator> {
+    vec![
+        Generator::Kruskal(Kruskal),
+        Generator::RecursiveBacktracker(RecursiveBacktracker),
+        Generator::Prim(Prim),
+        Generator::Wilson(Wilson),
+        Generator::AldousBroder(AldousBroder),
+        Generator::GrowingTree(GrowingTree::default()),
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::prelude::*;
+
+    /// Checks the passages join every cell exactly once.
+    fn assert_spanning_tree(grid: &Grid, passages: &[Passage]) {
+        assert_eq!(passages.len(), grid.cell_count().saturating_sub(1));
+
+        let mut trees = DisjointSet::new(grid.cell_count());
+        for passage in passages {
+            let next = grid.forward(passage.cell, passage.dim).unwrap();
+            assert!(trees.try_merge(passage.cell, next));
+        }
+    }
+
+    #[test]
+    fn disjoint_set_merge_roots() {
+        let mut trees = DisjointSet::new(3);
+
+        assert!(trees.try_merge(0, 1));
+        assert!(!trees.try_merge(0, 1));
+        assert!(!trees.try_merge(1, 0));
+
+        assert!(trees.try_merge(1, 2));
+        assert!(!trees.try_merge(0, 2));
+    }
+
+    #[test]
+    fn disjoint_set_merge_roots_alternate() {
+        let mut trees = DisjointSet::new(3);
+
+        assert!(trees.try_merge(0, 1));
+        assert!(!trees.try_merge(0, 1));
+        assert!(!trees.try_merge(1, 0));
+
+        assert!(trees.try_merge(0, 2));
+        assert!(!trees.try_merge(1, 2));
+    }
+
+    #[test]
+    fn disjoint_set_long_chain() {
+        let count = 1 << 20;
+        let mut trees = DisjointSet::new(count);
+        for cell in 1..count {
+            assert!(trees.try_merge(cell, cell - 1));
+        }
+
+        assert!(!trees.try_merge(0, count - 1));
+        assert_eq!(trees.get_root(count - 1), trees.get_root(0));
+    }
+
+    #[test]
+    fn kruskal_spans_large_grid() {
+        let mut rng = StdRng::seed_from_u64(684153987);
+        let grid = Grid::new(&[128, 128, 64]);
+        let passages = Kruskal.generate(&grid, &mut rng);
+
+        assert_spanning_tree(&grid, &passages);
+    }
+
+    #[test]
+    fn grid_neighbors_stay_in_bounds() {
+        let grid = Grid::new(&[3, 1, 2]);
+        let mut neighbors: Vec<_> = grid.neighbors(4).map(|(n, _)| n).collect();
+        neighbors.sort_unstable();
+        assert_eq!(neighbors, vec![1, 3, 5]);
+    }
+
+    #[test]
+    fn grid_index_round_trip() {
+        let grid = Grid::new(&[3, 1, 2]);
+        let mut coords = [0; 3];
+        for cell in 0..grid.cell_count() {
+            grid.write_coords(cell, &mut coords);
+            assert_eq!(grid.index_of(&coords), Some(cell));
+        }
+        assert_eq!(grid.index_of(&[3, 0, 0]), None);
+        assert_eq!(grid.index_of(&[0, 0]), None);
+    }
+
+    #[test]
+    fn grid_neighbors_wrap() {
+        let grid = Grid::wrapping(&[3, 2, 4], &[true, true, false]);
+        assert!(grid.wraps(0));
+        assert!(!grid.wraps(1));
+        assert_eq!(grid.forward(2, 0), Some(0));
+        assert_eq!(grid.backward(0, 0), Some(2));
+        assert_eq!(grid.forward(3, 1), None);
+        assert_eq!(grid.forward(18, 2), None);
+    }
+
+    #[test]
+    fn generators_span_wrapped_cells() {
+        let mut rng = StdRng::seed_from_u64(684153987);
+        for generator in all_generators() {
+            let grid = Grid::wrapping(&[5, 4, 3], &[true, false, true]);
+            let passages = generator.generate(&grid, &mut rng);
+            assert_spanning_tree(&grid, &passages);
+        }
+    }
+
+    #[test]
+    fn generators_span_all_cells() {
+        let mut rng = StdRng::seed_from_u64(684153987);
+        for generator in all_generators() {
+            for lengths in [&[1][..], &[5, 1, 1], &[4, 15, 2], &[3, 3, 3, 3, 3]] {
+                let grid = Grid::new(lengths);
+                let passages = generator.generate(&grid, &mut rng);
+                assert_spanning_tree(&grid, &passages);
+            }
+        }
+    }
+}
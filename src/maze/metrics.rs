@@ -0,0 +1,179 @@
+use super::{Coord, Maze};
+
+/// Measurements of how hard a maze is to solve between two cells.
+#[derive(Clone, Debug, PartialEq)]
+pub struct MazeMetrics {
+    /// The number of moves on the shortest route from the start to the goal.
+    pub solution_length: usize,
+    /// Cells with exactly one open passage.
+    pub dead_ends: usize,
+    /// The average number of open passages out of a cell along each dimension, from 0.0 to 2.0.
+    /// Summed over every dimension, this is the average number of ways out of a cell.
+    pub branching: Vec<f32>,
+    /// The fewest times the two viewed dimensions have to change to follow the shortest route,
+    /// starting from viewing the first two dimensions as levels do.
+    pub axis_changes: usize,
+    /// The average number of moves from a dead end back to the nearest junction.
+    pub average_dead_end_depth: f32,
+}
+
+impl<const DIMS: usize> Maze<DIMS> {
+    /// Measures the maze for a route from `start` to `goal`,
+    /// returning `None` if either is outside the maze or the goal can't be reached.
+    pub fn metrics(&self, start: &[Coord; DIMS], goal: &[Coord; DIMS]) -> Option<MazeMetrics> {
+        let solution = self.solve(start, goal)?;
+
+        let grid = &self.grid;
+        let open = |cell: usize| {
+            grid.neighbors(cell)
+                .filter(|(_, passage)| self.walks.contains(*passage))
+                .map(|(next, _)| next)
+        };
+        let degree: Vec<usize> = (0..grid.cell_count())
+            .map(|cell| open(cell).count())
+            .collect();
+
+        let mut branching = vec![0.0; DIMS];
+        for passage in self.walks.iter() {
+            // Each passage is a way out of both of the cells it joins.
+            branching[passage.dim] += 2.0 / grid.cell_count() as f32;
+        }
+
+        let dead_ends: Vec<usize> = (0..grid.cell_count())
+            .filter(|cell| degree[*cell] == 1)
+            .collect();
+        let total_depth: usize = dead_ends
+            .iter()
+            .map(|dead_end| {
+                let mut depth = 0;
+                let (mut prev, mut cell) = (*dead_end, *dead_end);
+                while depth == 0 || degree[cell] == 2 {
+                    match open(cell).find(|next| *next != prev) {
+                        Some(next) => (prev, cell) = (cell, next),
+                        None => break,
+                    }
+                    depth += 1;
+                }
+                depth
+            })
+            .sum();
+
+        Some(MazeMetrics {
+            solution_length: solution.moves(),
+            dead_ends: dead_ends.len(),
+            branching,
+            axis_changes: axis_changes(solution.steps.iter().map(|step| step.dim), DIMS),
+            average_dead_end_depth: if dead_ends.is_empty() {
+                0.0
+            } else {
+                total_depth as f32 / dead_ends.len() as f32
+            },
+        })
+    }
+}
+
+/// The fewest view changes needed to move along each of `dims` in turn, while viewing two
+/// dimensions at a time. Each change swaps out the viewed dimension needed furthest in the future.
+fn axis_changes(dims: impl Iterator<Item = usize>, dim_count: usize) -> usize {
+    let dims: Vec<usize> = dims.collect();
+    // The index each dimension is next moved along at, for every index of the route.
+    let mut next_use = vec![usize::MAX; dims.len()];
+    let mut upcoming = vec![usize::MAX; dim_count];
+    for (index, dim) in dims.iter().enumerate().rev() {
+        next_use[index] = upcoming[*dim];
+        upcoming[*dim] = index;
+    }
+
+    let mut viewed = [
+        (0, upcoming[0]),
+        (1, upcoming.get(1).copied().unwrap_or(usize::MAX)),
+    ];
+    let mut changes = 0;
+    for (index, dim) in dims.iter().enumerate() {
+        let slot = match viewed.iter().position(|(viewed, _)| viewed == dim) {
+            Some(slot) => slot,
+            None => {
+                changes += 1;
+                if viewed[0].1 >= viewed[1].1 {
+                    0
+                } else {
+                    1
+                }
+            }
+        };
+        viewed[slot] = (*dim, next_use[index]);
+    }
+    changes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::maze::*;
+    use rand::prelude::*;
+
+    fn fixed<const DIMS: usize>(
+        lengths: &[Coord; DIMS],
+        passages: &[(&[Coord], usize)],
+    ) -> Maze<DIMS> {
+        let passages = passages
+            .iter()
+            .map(|(cell, dim)| PassageRecord {
+                cell: cell.to_vec(),
+                dim: *dim,
+            })
+            .collect();
+        let mut rng = StdRng::seed_from_u64(684153987);
+        Maze::with_generator(lengths, &FixedPassages(passages), &mut rng)
+    }
+
+    #[test]
+    fn measures_corridor() {
+        let mut rng = StdRng::seed_from_u64(684153987);
+        let maze = Maze::new(&[5, 1, 1], &mut rng);
+
+        let metrics = maze.metrics(&[0, 0, 0], &[4, 0, 0]).unwrap();
+        assert_eq!(metrics.solution_length, 4);
+        assert_eq!(metrics.dead_ends, 2);
+        assert_eq!(metrics.branching, vec![1.6, 0.0, 0.0]);
+        assert_eq!(metrics.axis_changes, 0);
+        assert_eq!(metrics.average_dead_end_depth, 4.0);
+    }
+
+    #[test]
+    fn measures_junction() {
+        // A T, with its stem running up the middle column.
+        let maze = fixed(
+            &[3, 3],
+            &[(&[0, 0], 0), (&[1, 0], 0), (&[1, 0], 1), (&[1, 1], 1)],
+        );
+
+        let metrics = maze.metrics(&[0, 0], &[1, 2]).unwrap();
+        assert_eq!(metrics.solution_length, 3);
+        assert_eq!(metrics.dead_ends, 3);
+        assert_eq!(metrics.average_dead_end_depth, 4.0 / 3.0);
+        assert_eq!(maze.metrics(&[0, 0], &[2, 2]), None);
+    }
+
+    #[test]
+    fn counts_fewest_axis_changes() {
+        assert_eq!(axis_changes([0, 1, 0, 1].into_iter(), 2), 0);
+        // 0 is needed after 1 so it makes way for 2, then 1 makes way for 0 as it is done with.
+        assert_eq!(axis_changes([0, 2, 1, 0, 2].into_iter(), 3), 2);
+        assert_eq!(axis_changes([3, 2, 3, 2].into_iter(), 4), 2);
+    }
+
+    #[test]
+    fn spanning_trees_have_one_fewer_passage_than_cells() {
+        let mut rng = StdRng::seed_from_u64(684153987);
+        let lengths = [4, 3, 2, 3];
+        let maze = Maze::new(&lengths, &mut rng);
+        let metrics = maze.metrics(&[0; 4], &lengths.map(|l| l - 1)).unwrap();
+
+        let cells = maze.cell_count();
+        let exits: f32 = metrics.branching.iter().sum();
+        assert!((exits * cells as f32 / 2.0 - (cells - 1) as f32).abs() < 1e-3);
+        assert!(metrics.dead_ends >= 2);
+        assert!(metrics.average_dead_end_depth >= 1.0);
+    }
+}
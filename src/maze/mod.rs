@@ -0,0 +1,463 @@
+mod braid;
+mod dynamic;
+mod format;
+mod generator;
+mod metrics;
+mod passages;
+mod solver;
+mod version;
+
+pub use braid::*;
+pub use dynamic::*;
+pub use format::*;
+pub use generator::*;
+pub use metrics::*;
+pub use passages::*;
+pub use solver::*;
+pub use version::*;
+
+/// A position along one dimension of a maze, also used for the length of each dimension.
+pub type Coord = u16;
+
+#[derive(
+    Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Clone, Copy, serde::Serialize, serde::Deserialize,
+)]
+pub enum Direction {
+    Positive,
+    Negative,
+}
+
+impl Direction {
+    /// The direction leading back the way this one came.
+    pub fn reverse(self) -> Direction {
+        match self {
+            Direction::Positive => Direction::Negative,
+            Direction::Negative => Direction::Positive,
+        }
+    }
+}
+
+/// Movement through a maze, shared by the fixed and runtime dimensional mazes.
+pub trait NavigableMaze: Send + Sync {
+    /// The coordinates of a single cell.
+    type Cell: Clone + PartialEq + AsRef<[Coord]> + AsMut<[Coord]> + Send + Sync;
+
+    fn lengths(&self) -> &[Coord];
+    fn wraps(&self) -> &[bool];
+    /// The index math over the cells of the maze, the same the maze was generated with.
+    fn grid(&self) -> &Grid;
+    /// The first cell of the maze, with every coordinate at zero.
+    fn origin(&self) -> Self::Cell;
+    fn can_move(&self, point: &Self::Cell, dimension: usize) -> Option<bool>;
+    fn neighbor(&self, point: &Self::Cell, dimension: usize, dir: Direction) -> Option<Self::Cell>;
+    /// Every cell one step from `point`, ignoring walls, with the dimension and direction
+    /// stepped along. Cells outside the maze have no neighbors, the edges are decided by
+    /// `Grid::neighbor`.
+    fn neighbors<'a>(
+        &'a self,
+        point: &'a Self::Cell,
+    ) -> impl Iterator<Item = (usize, Direction, Self::Cell)> + 'a {
+        (0..self.lengths().len()).flat_map(move |dim| {
+            [Direction::Positive, Direction::Negative]
+                .into_iter()
+                .filter_map(move |dir| Some((dim, dir, self.neighbor(point, dim, dir)?)))
+        })
+    }
+    fn step(&self, point: &Self::Cell, dimension: usize, dir: Direction) -> Option<Self::Cell>;
+    fn farthest_from(&self, start: &Self::Cell) -> Option<Self::Cell>;
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct Maze<const DIMS: usize> {
+    grid: Grid,
+    walks: PassageSet,
+    lengths: [Coord; DIMS],
+    wraps: [bool; DIMS],
+}
+
+impl<const DIMS: usize> Default for Maze<DIMS> {
+    fn default() -> Self {
+        Self {
+            grid: Grid::new(&[1; DIMS]),
+            walks: PassageSet::new(1, DIMS),
+            lengths: [1; DIMS],
+            wraps: [false; DIMS],
+        }
+    }
+}
+
+impl<const DIMS: usize> Maze<DIMS> {
+    // Generate a maze with the provided number of side lengths.
+    pub fn new(lengths: &[Coord; DIMS], rng: &mut impl rand::Rng) -> Maze<DIMS> {
+        Self::with_generator(lengths, &Kruskal, rng)
+    }
+
+    // Generate a maze with the provided number of side lengths, using the provided algorithm.
+    pub fn with_generator(
+        lengths: &[Coord; DIMS],
+        generator: &impl MazeGenerator,
+        rng: &mut impl rand::Rng,
+    ) -> Maze<DIMS> {
+        Self::with_wrapping(lengths, &[false; DIMS], generator, rng)
+    }
+
+    // Generate a maze where the flagged dimensions wrap around from their last cell to their first.
+    pub fn with_wrapping(
+        lengths: &[Coord; DIMS],
+        wraps: &[bool; DIMS],
+        generator: &impl MazeGenerator,
+        rng: &mut impl rand::Rng,
+    ) -> Maze<DIMS> {
+        let grid = Grid::wrapping(lengths, wraps);
+        let mut walks = PassageSet::new(grid.cell_count(), DIMS);
+        walks.extend(generator.generate(&grid, rng));
+
+        let mut wraps = [false; DIMS];
+        for (dim, wrap) in wraps.iter_mut().enumerate() {
+            *wrap = grid.wraps(dim);
+        }
+
+        Maze::<DIMS> {
+            grid,
+            lengths: *lengths,
+            wraps,
+            walks,
+        }
+    }
+
+    /// The linear index of a cell, if it is inside the maze.
+    /// Cells are numbered with the first dimension as the lowest power, from zero to `cell_count`.
+    pub fn index_of(&self, point: &[Coord; DIMS]) -> Option<usize> {
+        self.grid.index_of(point)
+    }
+
+    /// The cell at a linear index, the inverse of `index_of`.
+    pub fn cell_at(&self, index: usize) -> Option<[Coord; DIMS]> {
+        if index >= self.grid.cell_count() {
+            return None;
+        }
+        let mut cell = [0; DIMS];
+        self.grid.write_coords(index, &mut cell);
+        Some(cell)
+    }
+
+    /// The number of cells in the maze.
+    pub fn cell_count(&self) -> usize {
+        self.grid.cell_count()
+    }
+
+    /// Every cell of the maze, in the order of their linear index.
+    pub fn cells(&self) -> impl Iterator<Item = [Coord; DIMS]> + '_ {
+        (0..self.cell_count()).map(|index| self.cell_at(index).unwrap())
+    }
+
+    pub fn can_move(&self, point: &[Coord; DIMS], dimension: usize) -> Option<bool> {
+        let cell = self.index_of(point)?;
+        self.neighbor(point, dimension, Direction::Positive)?;
+        Some(self.walks.contains(Passage {
+            cell,
+            dim: dimension,
+        }))
+    }
+
+    /// The cell one step from `point` along `dimension`, ignoring walls.
+    /// Returns `None` when `point` is outside the maze, or when stepping off the end of a
+    /// dimension that doesn't wrap.
+    pub fn neighbor(
+        &self,
+        point: &[Coord; DIMS],
+        dimension: usize,
+        dir: Direction,
+    ) -> Option<[Coord; DIMS]> {
+        if dimension >= DIMS {
+            return None;
+        }
+        let next = self.grid.neighbor(self.index_of(point)?, dimension, dir)?;
+        self.cell_at(next)
+    }
+
+    /// The cell reached by moving from `point` along `dimension`, if a passage leads there.
+    pub fn step(
+        &self,
+        point: &[Coord; DIMS],
+        dimension: usize,
+        dir: Direction,
+    ) -> Option<[Coord; DIMS]> {
+        let target_point = self.neighbor(point, dimension, dir)?;
+        if self.joins(point, dimension, dir, &target_point) {
+            Some(target_point)
+        } else {
+            None
+        }
+    }
+
+    /// Whether a passage joins `point` to `next`, its neighbor along `dimension` in `dir`.
+    fn joins(
+        &self,
+        point: &[Coord; DIMS],
+        dimension: usize,
+        dir: Direction,
+        next: &[Coord; DIMS],
+    ) -> bool {
+        // Passages are stored on the cell at their negative end.
+        let from = match dir {
+            Direction::Positive => point,
+            Direction::Negative => next,
+        };
+        self.index_of(from).is_some_and(|cell| {
+            self.walks.contains(Passage {
+                cell,
+                dim: dimension,
+            })
+        })
+    }
+
+    /// Every open passage, as the cell it leaves from and the dimension it leads along.
+    pub fn passages(&self) -> impl Iterator<Item = ([Coord; DIMS], usize)> + '_ {
+        self.walks
+            .iter()
+            .map(|Passage { cell, dim }| (self.cell_at(cell).unwrap(), dim))
+    }
+
+    #[inline]
+    pub fn lengths(&self) -> &[Coord; DIMS] {
+        &self.lengths
+    }
+
+    /// Which dimensions wrap around, dimensions shorter than three never wrap.
+    #[inline]
+    pub fn wraps(&self) -> &[bool; DIMS] {
+        &self.wraps
+    }
+}
+
+impl<const DIMS: usize> NavigableMaze for Maze<DIMS> {
+    type Cell = [Coord; DIMS];
+
+    fn lengths(&self) -> &[Coord] {
+        Maze::lengths(self)
+    }
+
+    fn wraps(&self) -> &[bool] {
+        Maze::wraps(self)
+    }
+
+    fn grid(&self) -> &Grid {
+        &self.grid
+    }
+
+    fn origin(&self) -> Self::Cell {
+        [0; DIMS]
+    }
+
+    fn can_move(&self, point: &Self::Cell, dimension: usize) -> Option<bool> {
+        Maze::can_move(self, point, dimension)
+    }
+
+    fn neighbor(&self, point: &Self::Cell, dimension: usize, dir: Direction) -> Option<Self::Cell> {
+        Maze::neighbor(self, point, dimension, dir)
+    }
+
+    fn step(&self, point: &Self::Cell, dimension: usize, dir: Direction) -> Option<Self::Cell> {
+        Maze::step(self, point, dimension, dir)
+    }
+
+    fn farthest_from(&self, start: &Self::Cell) -> Option<Self::Cell> {
+        Maze::farthest_from(self, start)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::prelude::*;
+    use std::collections::HashSet;
+
+    #[test]
+    fn cell_at_verify() {
+        let maze = Maze::new(&[2], &mut StdRng::seed_from_u64(684153987));
+        assert_eq!(maze.cell_at(0), Some([0]));
+        assert_eq!(maze.cell_at(1), Some([1]));
+        assert_eq!(maze.cell_at(2), None);
+    }
+
+    #[test]
+    fn converts_cells_and_indices() {
+        let mut rng = StdRng::seed_from_u64(684153987);
+        let maze = Maze::new(&[3, 1, 2], &mut rng);
+
+        assert_eq!(maze.cell_count(), 6);
+        assert_eq!(
+            maze.cells().collect::<Vec<_>>(),
+            vec![
+                [0, 0, 0],
+                [1, 0, 0],
+                [2, 0, 0],
+                [0, 0, 1],
+                [1, 0, 1],
+                [2, 0, 1]
+            ]
+        );
+        for (index, cell) in maze.cells().enumerate() {
+            assert_eq!(maze.index_of(&cell), Some(index));
+            assert_eq!(maze.cell_at(index), Some(cell));
+        }
+        assert_eq!(maze.index_of(&[0, 1, 0]), None);
+        assert_eq!(maze.cell_at(6), None);
+    }
+
+    #[test]
+    fn verify_generates() {
+        let mut rng = StdRng::seed_from_u64(684153987);
+        let maze = Maze::new(&[5, 5, 5, 5, 5], &mut rng);
+
+        assert_eq!(maze.can_move(&[1, 2, 52, 2, 2], 2), None);
+    }
+
+    #[test]
+    fn verify_generates_wrapped() {
+        let mut rng = StdRng::seed_from_u64(684153987);
+        let maze = Maze::with_wrapping(&[5, 2], &[true, true], &Kruskal, &mut rng);
+
+        assert_eq!(maze.wraps(), &[true, false]);
+        assert_eq!(maze.neighbor(&[4, 0], 0, Direction::Positive), Some([0, 0]));
+        assert_eq!(maze.neighbor(&[0, 1], 0, Direction::Negative), Some([4, 1]));
+        assert_eq!(maze.neighbor(&[0, 1], 1, Direction::Positive), None);
+        assert!(maze.can_move(&[4, 0], 0).is_some());
+        assert_eq!(maze.can_move(&[4, 1], 1), None);
+    }
+
+    #[test]
+    fn verify_generates_single() {
+        let mut rng = StdRng::seed_from_u64(684153987);
+        let maze = Maze::new(&[5, 1, 1], &mut rng);
+
+        assert_eq!(maze.can_move(&[0, 0, 0], 0), Some(true));
+        assert_eq!(maze.can_move(&[1, 0, 0], 0), Some(true));
+        assert_eq!(maze.can_move(&[2, 0, 0], 0), Some(true));
+        assert_eq!(maze.can_move(&[3, 0, 0], 0), Some(true));
+        assert_eq!(maze.can_move(&[4, 0, 0], 0), None);
+    }
+
+    #[test]
+    fn neighbors_stop_at_edges() {
+        let mut rng = StdRng::seed_from_u64(684153987);
+        let maze = Maze::with_wrapping(&[3, 2, 1], &[true, true, true], &Kruskal, &mut rng);
+
+        let neighbors: Vec<_> = maze.neighbors(&[0, 1, 0]).collect();
+        assert_eq!(
+            neighbors,
+            vec![
+                (0, Direction::Positive, [1, 1, 0]),
+                (0, Direction::Negative, [2, 1, 0]),
+                (1, Direction::Negative, [0, 0, 0]),
+            ]
+        );
+        assert_eq!(maze.neighbors(&[0, 2, 0]).count(), 0);
+        assert_eq!(maze.neighbor(&[3, 0, 0], 1, Direction::Positive), None);
+        assert_eq!(maze.can_move(&[0, 1, 0], 1), None);
+        assert_eq!(maze.can_move(&[0, 0, 0], 2), None);
+    }
+
+    /// A maze of `DIMS` dimensions with random lengths, wrapping and perfect generator.
+    fn random_maze<const DIMS: usize>(rng: &mut impl Rng) -> Maze<DIMS> {
+        let generators = all_generators();
+        let generator = &generators[rng.gen_range(0..generators.len())];
+        let mut lengths = [0; DIMS];
+        let mut wraps = [false; DIMS];
+        for (length, wrap) in lengths.iter_mut().zip(wraps.iter_mut()) {
+            *length = rng.gen_range(1..=4);
+            *wrap = rng.gen_bool(0.25);
+        }
+        Maze::with_wrapping(&lengths, &wraps, generator, rng)
+    }
+
+    /// Checks the maze has one passage fewer than cells, and every cell is reached exactly once
+    /// walking out from the origin, so the maze is connected and has no loops.
+    fn assert_spanning_tree<const DIMS: usize>(maze: &Maze<DIMS>) {
+        assert_eq!(
+            maze.passages().count(),
+            maze.cell_count() - 1,
+            "{:?}",
+            maze.lengths()
+        );
+
+        let mut visited = HashSet::from([[0; DIMS]]);
+        let mut pending = vec![([0; DIMS], None)];
+        while let Some((cell, parent)) = pending.pop() {
+            for (dim, dir, _) in maze.neighbors(&cell) {
+                if let Some(next) = maze.step(&cell, dim, dir) {
+                    if Some(next) == parent {
+                        continue;
+                    }
+                    assert!(visited.insert(next), "{:?} closes a loop", next);
+                    pending.push((next, Some(cell)));
+                }
+            }
+        }
+        assert_eq!(visited.len(), maze.cell_count(), "{:?}", maze.lengths());
+    }
+
+    /// Checks moving between two neighbors is allowed in both directions or in neither.
+    fn assert_symmetric_moves<const DIMS: usize>(maze: &Maze<DIMS>) {
+        for cell in maze.cells() {
+            for (dim, dir, next) in maze.neighbors(&cell) {
+                assert!(maze
+                    .neighbors(&next)
+                    .any(|n| n == (dim, dir.reverse(), cell)));
+                let open = maze.step(&cell, dim, dir);
+                assert_eq!(
+                    open.is_some(),
+                    maze.step(&next, dim, dir.reverse()).is_some()
+                );
+                let (from, to) = match dir {
+                    Direction::Positive => (cell, next),
+                    Direction::Negative => (next, cell),
+                };
+                assert_eq!(maze.can_move(&from, dim), Some(open.is_some()));
+                assert_eq!(
+                    maze.step(&from, dim, Direction::Positive).is_some(),
+                    open.is_some()
+                );
+                assert_eq!(maze.neighbor(&from, dim, Direction::Positive), Some(to));
+            }
+        }
+    }
+
+    /// Checks every linear index maps to a distinct cell and back.
+    fn assert_index_bijection<const DIMS: usize>(maze: &Maze<DIMS>, rng: &mut impl Rng) {
+        let lengths = maze.lengths();
+        let mut seen = HashSet::new();
+        for index in 0..maze.cell_count() {
+            let cell = maze.cell_at(index).unwrap();
+            assert!(seen.insert(cell));
+            assert_eq!(maze.index_of(&cell), Some(index));
+        }
+        assert_eq!(maze.cell_at(maze.cell_count()), None);
+
+        let dim = rng.gen_range(0..DIMS);
+        let mut outside = maze.cell_at(rng.gen_range(0..maze.cell_count())).unwrap();
+        outside[dim] = lengths[dim] + rng.gen_range(0..3);
+        assert_eq!(maze.index_of(&outside), None);
+    }
+
+    fn check_properties<const DIMS: usize>(rng: &mut impl Rng) {
+        let maze = random_maze::<DIMS>(rng);
+        assert_spanning_tree(&maze);
+        assert_symmetric_moves(&maze);
+        assert_index_bijection(&maze, rng);
+    }
+
+    #[test]
+    fn random_mazes_hold_invariants() {
+        for seed in 0..50 {
+            let mut rng = StdRng::seed_from_u64(seed);
+            check_properties::<1>(&mut rng);
+            check_properties::<2>(&mut rng);
+            check_properties::<3>(&mut rng);
+            check_properties::<4>(&mut rng);
+            check_properties::<5>(&mut rng);
+            check_properties::<6>(&mut rng);
+        }
+    }
+}
@@ -0,0 +1,110 @@
+use super::Passage;
+
+/// The open passages of a maze, stored as one bit per cell and dimension.
+///
+/// Bit `cell * dims + dim` is set when the passage from `cell` to the next cell along `dim`
+/// in the positive direction is open.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct PassageSet {
+    bits: Vec<u64>,
+    dims: usize,
+}
+
+impl PassageSet {
+    pub fn new(cell_count: usize, dims: usize) -> Self {
+        Self {
+            bits: vec![0; (cell_count * dims).div_ceil(64)],
+            dims,
+        }
+    }
+
+    #[inline]
+    fn bit(&self, passage: Passage) -> (usize, u64) {
+        let bit = passage.cell * self.dims + passage.dim;
+        (bit / 64, 1 << (bit % 64))
+    }
+
+    /// Opens a passage, returning true if it was previously closed.
+    pub fn insert(&mut self, passage: Passage) -> bool {
+        let (word, mask) = self.bit(passage);
+        let closed = self.bits[word] & mask == 0;
+        self.bits[word] |= mask;
+        closed
+    }
+
+    /// Whether a passage is open, passages outside of the maze are never open.
+    #[inline]
+    pub fn contains(&self, passage: Passage) -> bool {
+        if passage.dim >= self.dims {
+            return false;
+        }
+        let (word, mask) = self.bit(passage);
+        self.bits.get(word).map(|w| w & mask != 0).unwrap_or(false)
+    }
+
+    /// The number of open passages.
+    pub fn len(&self) -> usize {
+        self.bits.iter().map(|w| w.count_ones() as usize).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.bits.iter().all(|w| *w == 0)
+    }
+
+    /// Every open passage, ordered by cell and then dimension.
+    pub fn iter(&self) -> impl Iterator<Item = Passage> + '_ {
+        self.bits.iter().enumerate().flat_map(move |(word, bits)| {
+            (0..64)
+                .filter(move |bit| bits & (1 << bit) != 0)
+                .map(move |bit| {
+                    let index = word * 64 + bit;
+                    Passage {
+                        cell: index / self.dims,
+                        dim: index % self.dims,
+                    }
+                })
+        })
+    }
+}
+
+impl Extend<Passage> for PassageSet {
+    fn extend<T: IntoIterator<Item = Passage>>(&mut self, iter: T) {
+        for passage in iter {
+            self.insert(passage);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn insert_and_contains() {
+        let mut set = PassageSet::new(40, 3);
+        let passage = Passage { cell: 30, dim: 2 };
+
+        assert!(!set.contains(passage));
+        assert!(set.insert(passage));
+        assert!(!set.insert(passage));
+        assert!(set.contains(passage));
+        assert!(!set.contains(Passage { cell: 30, dim: 1 }));
+        assert!(!set.contains(Passage { cell: 30, dim: 3 }));
+        assert!(!set.contains(Passage { cell: 400, dim: 0 }));
+        assert_eq!(set.len(), 1);
+    }
+
+    #[test]
+    fn iterates_in_order() {
+        let mut set = PassageSet::new(100, 2);
+        let passages = [
+            Passage { cell: 0, dim: 1 },
+            Passage { cell: 31, dim: 0 },
+            Passage { cell: 32, dim: 0 },
+            Passage { cell: 99, dim: 1 },
+        ];
+        set.extend(passages);
+
+        assert_eq!(set.iter().collect::<Vec<_>>(), passages);
+    }
+}
@@ -0,0 +1,165 @@
+use std::collections::{HashMap, VecDeque};
+
+use super::{Coord, Direction, Maze, NavigableMaze};
+
+/// A single move through the maze, along `dim` in the direction `dir`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Step {
+    pub dim: usize,
+    pub dir: Direction,
+}
+
+/// The shortest route between two cells of a maze.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Solution<const DIMS: usize> {
+    /// Every cell on the route, starting with the start and ending with the goal.
+    pub cells: Vec<[Coord; DIMS]>,
+    /// The move taken out of each cell, one shorter than `cells`.
+    pub steps: Vec<Step>,
+}
+
+impl<const DIMS: usize> Solution<DIMS> {
+    /// The number of moves needed to reach the goal.
+    #[inline]
+    pub fn moves(&self) -> usize {
+        self.steps.len()
+    }
+}
+
+/// Each reached cell, mapped to the cell and step it was first reached from.
+type Visits<const DIMS: usize> = HashMap<[Coord; DIMS], Option<([Coord; DIMS], Step)>>;
+
+impl<const DIMS: usize> Maze<DIMS> {
+    /// Finds the shortest route from `start` to `goal` with a breadth first search,
+    /// returning `None` if either is outside the maze or the goal can't be reached.
+    pub fn solve(&self, start: &[Coord; DIMS], goal: &[Coord; DIMS]) -> Option<Solution<DIMS>> {
+        self.index_of(start)?;
+        self.index_of(goal)?;
+
+        let (came_from, _) = self.search(start, Some(goal));
+        came_from.get(goal)?;
+
+        let mut cells = vec![*goal];
+        let mut steps = Vec::new();
+        while let Some((prev, step)) = came_from[cells.last().unwrap()] {
+            cells.push(prev);
+            steps.push(step);
+        }
+        cells.reverse();
+        steps.reverse();
+
+        Some(Solution { cells, steps })
+    }
+
+    /// Finds the cell that takes the most moves to reach from `start`,
+    /// returning `None` if the start is outside the maze.
+    pub fn farthest_from(&self, start: &[Coord; DIMS]) -> Option<[Coord; DIMS]> {
+        self.index_of(start)?;
+        let (_, last) = self.search(start, None);
+        Some(last)
+    }
+
+    /// Breadth first search from `start`, stopping early if `goal` is reached.
+    /// Returns the visited cells and the last cell searched.
+    fn search(
+        &self,
+        start: &[Coord; DIMS],
+        goal: Option<&[Coord; DIMS]>,
+    ) -> (Visits<DIMS>, [Coord; DIMS]) {
+        let mut came_from = Visits::new();
+        let mut pending = VecDeque::new();
+        let mut last = *start;
+        came_from.insert(*start, None);
+        pending.push_back(*start);
+
+        while let Some(cell) = pending.pop_front() {
+            last = cell;
+            if Some(&cell) == goal {
+                break;
+            }
+            for (dim, dir, next) in self.neighbors(&cell) {
+                if self.joins(&cell, dim, dir, &next) {
+                    came_from.entry(next).or_insert_with(|| {
+                        pending.push_back(next);
+                        Some((cell, Step { dim, dir }))
+                    });
+                }
+            }
+        }
+
+        (came_from, last)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::maze::*;
+    use rand::prelude::*;
+
+    #[test]
+    fn solves_single_corridor() {
+        let mut rng = StdRng::seed_from_u64(684153987);
+        let maze = Maze::new(&[5, 1, 1], &mut rng);
+
+        let solution = maze.solve(&[4, 0, 0], &[1, 0, 0]).unwrap();
+        assert_eq!(
+            solution.cells,
+            vec![[4, 0, 0], [3, 0, 0], [2, 0, 0], [1, 0, 0]]
+        );
+        assert_eq!(solution.moves(), 3);
+        assert!(solution.steps.iter().all(|step| *step
+            == Step {
+                dim: 0,
+                dir: Direction::Negative
+            }));
+    }
+
+    #[test]
+    fn solves_to_self() {
+        let mut rng = StdRng::seed_from_u64(684153987);
+        let maze = Maze::new(&[3, 3], &mut rng);
+
+        let solution = maze.solve(&[1, 1], &[1, 1]).unwrap();
+        assert_eq!(solution.cells, vec![[1, 1]]);
+        assert!(solution.steps.is_empty());
+    }
+
+    #[test]
+    fn rejects_out_of_bounds() {
+        let mut rng = StdRng::seed_from_u64(684153987);
+        let maze = Maze::new(&[3, 3], &mut rng);
+
+        assert_eq!(maze.solve(&[0, 0], &[3, 0]), None);
+    }
+
+    #[test]
+    fn finds_farthest_cell() {
+        let mut rng = StdRng::seed_from_u64(684153987);
+        let maze = Maze::new(&[5, 1, 1], &mut rng);
+
+        assert_eq!(maze.farthest_from(&[1, 0, 0]), Some([4, 0, 0]));
+        assert_eq!(maze.farthest_from(&[5, 0, 0]), None);
+    }
+
+    #[test]
+    fn every_generated_maze_is_solvable() {
+        let mut rng = StdRng::seed_from_u64(684153987);
+        for generator in all_generators().iter() {
+            let lengths = [4, 3, 2, 3];
+            let maze = Maze::with_generator(&lengths, generator, &mut rng);
+            let goal = lengths.map(|l| l - 1);
+            let solution = maze.solve(&[0; 4], &goal).unwrap();
+
+            assert_eq!(solution.cells.first(), Some(&[0; 4]));
+            assert_eq!(solution.cells.last(), Some(&goal));
+            for (cell, (step, next)) in solution
+                .cells
+                .iter()
+                .zip(solution.steps.iter().zip(solution.cells.iter().skip(1)))
+            {
+                assert_eq!(maze.step(cell, step.dim, step.dir), Some(*next));
+            }
+        }
+    }
+}
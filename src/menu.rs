@@ -0,0 +1,695 @@
+use std::ops::RangeInclusive;
+
+use bevy::{app::AppExit, ecs::system::SystemParam, prelude::*};
+
+use crate::{
+    level::{
+        Action, ActionBindings, ActionPressed, Axis, AxisRotation, DimensionLength, Direction,
+        GoalPlacement, InputDevice, LayoutSource, LevelAssets, LevelInfo, LevelPack,
+        LevelPackProgress, LoadLevel, MazeLevelAsset, RngSource,
+    },
+    maze::{Coord, Generator, GeneratorVersion},
+    AppState,
+};
+
+const DIMENSIONS: RangeInclusive<u8> = 2..=6;
+const LENGTHS: RangeInclusive<u8> = 2..=10;
+const MAX_SEED_LENGTH: usize = 32;
+/// The shape of the maze of the day, fixed so everyone plays the same one.
+const DAILY_LENGTHS: [Coord; 4] = [5; 4];
+const FOLD_TIMES: RangeInclusive<f32> = 0.0..=1.0;
+const FOLD_TIME_STEP: f32 = 0.1;
+
+pub struct MenuPlugin;
+
+impl Plugin for MenuPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<MenuState>()
+            .add_event::<MenuActivated>()
+            .add_system_set(
+                SystemSet::on_update(AppState::MainMenu)
+                    .with_system(menu_navigation)
+                    .with_system(menu_activation.after(menu_navigation))
+                    .with_system(refresh_menu_on_load)
+                    .with_system(
+                        render_menu
+                            .after(menu_activation)
+                            .after(refresh_menu_on_load),
+                    ),
+            )
+            .add_system_set(SystemSet::on_enter(AppState::MainMenu).with_system(open_main_page))
+            .add_system_set(SystemSet::on_exit(AppState::MainMenu).with_system(despawn_menu))
+            .add_system_set(
+                SystemSet::on_update(AppState::Paused)
+                    .with_system(menu_navigation)
+                    .with_system(pause_activation.after(menu_navigation))
+                    .with_system(render_menu.after(pause_activation)),
+            )
+            .add_system_set(SystemSet::on_enter(AppState::Paused).with_system(open_pause_page))
+            .add_system_set(SystemSet::on_exit(AppState::Paused).with_system(despawn_menu));
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum MenuPage {
+    Main,
+    NewGame,
+    LevelSelect,
+    Settings,
+    /// Shown over the level while it's paused.
+    Pause,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum MenuItem {
+    Continue,
+    NewGame,
+    /// The maze of the day, generated from the local date.
+    Daily,
+    LevelSelect,
+    Settings,
+    Quit,
+    Dimensions,
+    Length,
+    Seed,
+    /// Fills the seed in from the last maze played.
+    LastSeed,
+    Start,
+    /// A level of the pack, by index.
+    Level(usize),
+    FoldTime,
+    ResetControls,
+    Resume,
+    Restart,
+    /// The current level's shape with a new seed.
+    Reseed,
+    QuitToMenu,
+    Back,
+}
+
+/// Which menu page is open, and the choices made for a new maze.
+pub struct MenuState {
+    page: MenuPage,
+    selected: usize,
+    dimensions: u8,
+    length: u8,
+    /// The seed typed so far, used as a number if it is one and hashed otherwise.
+    /// A random seed is used when empty.
+    seed: String,
+    /// The version the seed is generated with, older than the latest only for a reused seed.
+    version: GeneratorVersion,
+}
+
+impl Default for MenuState {
+    fn default() -> Self {
+        Self {
+            page: MenuPage::Main,
+            selected: 0,
+            dimensions: 3,
+            length: 4,
+            seed: String::new(),
+            version: GeneratorVersion::LATEST,
+        }
+    }
+}
+
+impl MenuState {
+    fn items(&self, level_count: usize) -> Vec<MenuItem> {
+        match self.page {
+            MenuPage::Main => vec![
+                MenuItem::Continue,
+                MenuItem::NewGame,
+                MenuItem::Daily,
+                MenuItem::LevelSelect,
+                MenuItem::Settings,
+                MenuItem::Quit,
+            ],
+            MenuPage::NewGame => vec![
+                MenuItem::Dimensions,
+                MenuItem::Length,
+                MenuItem::Seed,
+                MenuItem::LastSeed,
+                MenuItem::Start,
+                MenuItem::Back,
+            ],
+            MenuPage::LevelSelect => (0..level_count)
+                .map(MenuItem::Level)
+                .chain([MenuItem::Back])
+                .collect(),
+            MenuPage::Settings => vec![MenuItem::FoldTime, MenuItem::ResetControls, MenuItem::Back],
+            MenuPage::Pause => vec![
+                MenuItem::Resume,
+                MenuItem::Restart,
+                MenuItem::Reseed,
+                MenuItem::QuitToMenu,
+            ],
+        }
+    }
+
+    fn open(&mut self, page: MenuPage) {
+        self.page = page;
+        self.selected = 0;
+    }
+
+    /// Moves the highlight up for `Positive` and down for `Negative`, wrapping at either end.
+    fn step_selection(&mut self, dir: Direction, count: usize) {
+        if count == 0 {
+            return;
+        }
+        self.selected = match dir {
+            Direction::Positive => (self.selected + count - 1) % count,
+            Direction::Negative => (self.selected + 1) % count,
+        };
+    }
+
+    /// Steps the value of a picker, returning whether `item` is one.
+    fn adjust(&mut self, item: MenuItem, dir: Direction) -> bool {
+        match item {
+            MenuItem::Dimensions => self.dimensions = step(self.dimensions, dir, DIMENSIONS),
+            MenuItem::Length => self.length = step(self.length, dir, LENGTHS),
+            _ => return false,
+        }
+        true
+    }
+
+    /// Steps a picker up, going back around to the smallest value after the largest.
+    fn cycle(&mut self, item: MenuItem) {
+        match item {
+            MenuItem::Dimensions if self.dimensions == *DIMENSIONS.end() => {
+                self.dimensions = *DIMENSIONS.start()
+            }
+            MenuItem::Length if self.length == *LENGTHS.end() => self.length = *LENGTHS.start(),
+            item => {
+                self.adjust(item, Direction::Positive);
+            }
+        }
+    }
+
+    /// Adds a typed character to the seed, or removes the last one on backspace.
+    /// Typing makes a new seed, so it is generated with the latest version.
+    fn type_seed(&mut self, c: char) {
+        self.version = GeneratorVersion::LATEST;
+        if c == '\u{8}' {
+            self.seed.pop();
+        } else if !c.is_control() && self.seed.chars().count() < MAX_SEED_LENGTH {
+            self.seed.push(c);
+        }
+    }
+
+    /// The level described by the new maze choices.
+    fn custom_level(&self) -> LoadLevel {
+        let rng_source = if self.seed.is_empty() {
+            RngSource::Entropy
+        } else if let Ok(seed) = self.seed.parse() {
+            RngSource::Seeded(seed)
+        } else {
+            RngSource::Phrase(self.seed.clone())
+        };
+        LoadLevel {
+            layout: LayoutSource::Generated {
+                rng_source,
+                version: self.version,
+                generator: Generator::default(),
+                braid: 0.0,
+            },
+            dimensions: DimensionLength::from_lengths(&vec![
+                Coord::from(self.length);
+                self.dimensions as usize
+            ]),
+            goal: GoalPlacement::Farthest,
+            ..default()
+        }
+    }
+}
+
+fn daily_level() -> LoadLevel {
+    LoadLevel {
+        title: "Maze of the day".into(),
+        layout: LayoutSource::generated(RngSource::Daily),
+        dimensions: DimensionLength::from_lengths(&DAILY_LENGTHS),
+        goal: GoalPlacement::Farthest,
+        ..default()
+    }
+}
+
+fn step(value: u8, dir: Direction, range: RangeInclusive<u8>) -> u8 {
+    match dir {
+        Direction::Positive => value.saturating_add(1),
+        Direction::Negative => value.saturating_sub(1),
+    }
+    .clamp(*range.start(), *range.end())
+}
+
+/// Sent when the player picks a menu entry.
+struct MenuActivated(MenuItem);
+
+#[derive(Component)]
+struct MenuRoot;
+
+/// The menu entry a button picks, by index into the open page.
+#[derive(Component)]
+struct MenuButton(usize);
+
+fn is_enabled(
+    item: MenuItem,
+    progress: &LevelPackProgress,
+    packs: &Assets<LevelPack>,
+    levels: &Assets<MazeLevelAsset>,
+) -> bool {
+    match item {
+        MenuItem::Continue => progress.level_at(progress.level, packs, levels).is_some(),
+        MenuItem::Level(index) => {
+            progress.is_unlocked(index) && progress.level_at(index, packs, levels).is_some()
+        }
+        MenuItem::LastSeed => progress.last_seed.is_some(),
+        _ => true,
+    }
+}
+
+fn item_label(
+    item: MenuItem,
+    state: &MenuState,
+    progress: &LevelPackProgress,
+    packs: &Assets<LevelPack>,
+    levels: &Assets<MazeLevelAsset>,
+    fold_time: f32,
+) -> String {
+    match item {
+        MenuItem::Continue => match progress.level_at(progress.level, packs, levels) {
+            Some(level) => format!("Continue: {}", level.title),
+            None if (1..=progress.level).contains(&progress.level_count(packs)) => {
+                "Continue: all levels solved".into()
+            }
+            None => "Continue".into(),
+        },
+        MenuItem::NewGame => "New Maze".into(),
+        MenuItem::Daily => "Maze of the day".into(),
+        MenuItem::LevelSelect => "Levels".into(),
+        MenuItem::Settings => "Settings".into(),
+        MenuItem::Quit => "Quit".into(),
+        MenuItem::Dimensions => format!("Dimensions: < {} >", state.dimensions),
+        MenuItem::Length => format!("Side length: < {} >", state.length),
+        MenuItem::Seed if state.seed.is_empty() => "Seed: random".into(),
+        MenuItem::Seed => format!("Seed: {} ({})", state.seed, state.version),
+        MenuItem::LastSeed => match progress.last_seed {
+            Some((seed, version)) => format!("Use last seed: {} ({})", seed, version),
+            None => "Use last seed".into(),
+        },
+        MenuItem::Start => "Start".into(),
+        MenuItem::Level(index) if !progress.is_unlocked(index) => {
+            format!("{}. Locked", index + 1)
+        }
+        MenuItem::Level(index) => match progress.level_at(index, packs, levels) {
+            Some(level) => format!("{}. {}", index + 1, level.title),
+            None => format!("{}. Loading", index + 1),
+        },
+        MenuItem::FoldTime => format!("Fold time: < {:.1}s >", fold_time),
+        MenuItem::ResetControls => "Reset controls".into(),
+        MenuItem::Resume => "Resume".into(),
+        MenuItem::Restart => "Restart".into(),
+        MenuItem::Reseed => "New maze".into(),
+        MenuItem::QuitToMenu => "Quit to menu".into(),
+        MenuItem::Back => "Back".into(),
+    }
+}
+
+/// Everything the player can work the menu with.
+#[derive(SystemParam)]
+struct MenuInput<'w, 's> {
+    actions: EventReader<'w, 's, ActionPressed>,
+    chars: EventReader<'w, 's, ReceivedCharacter>,
+    buttons: Query<'w, 's, (&'static Interaction, &'static MenuButton), Changed<Interaction>>,
+    /// The device last used, as moves on a keyboard are also typed.
+    device: Res<'w, InputDevice>,
+}
+
+/// Moves the highlight, steps pickers and types the seed, sending the entries the player picks.
+fn menu_navigation(
+    input: MenuInput,
+    mut state: ResMut<MenuState>,
+    mut rotation: ResMut<AxisRotation>,
+    packs: Res<Assets<LevelPack>>,
+    progress: Res<LevelPackProgress>,
+    mut activated: EventWriter<MenuActivated>,
+) {
+    let MenuInput {
+        mut actions,
+        mut chars,
+        buttons,
+        device,
+    } = input;
+    let items = state.items(progress.level_count(&packs));
+    // Only keys pressed while the seed is already highlighted are typed, not those moving onto it.
+    let typing_seed = items.get(state.selected) == Some(&MenuItem::Seed);
+    for ActionPressed(action) in actions.iter() {
+        let item = items.get(state.selected).copied();
+        // Keys typed into the seed are bound to moves too, so they mustn't move the highlight.
+        let typing = item == Some(MenuItem::Seed) && *device == InputDevice::Keyboard;
+        match *action {
+            Action::Move(..) if typing => {}
+            Action::Move(Axis::X, dir) => state.step_selection(dir, items.len()),
+            Action::Move(Axis::Y, dir) => match item {
+                Some(MenuItem::FoldTime) => {
+                    let delta = match dir {
+                        Direction::Positive => FOLD_TIME_STEP,
+                        Direction::Negative => -FOLD_TIME_STEP,
+                    };
+                    rotation.duration =
+                        (rotation.duration + delta).clamp(*FOLD_TIMES.start(), *FOLD_TIMES.end());
+                }
+                Some(item) => {
+                    state.adjust(item, dir);
+                }
+                None => {}
+            },
+            Action::Confirm => {
+                if let Some(item) = item {
+                    activated.send(MenuActivated(item));
+                }
+            }
+            Action::Back => activated.send(MenuActivated(MenuItem::Back)),
+            Action::ShiftAxis(..) => {}
+        }
+    }
+
+    // Characters are read every frame, so ones typed elsewhere aren't left for the seed.
+    for c in chars.iter() {
+        if typing_seed {
+            state.type_seed(c.char);
+        }
+    }
+
+    for (interaction, MenuButton(index)) in buttons.iter() {
+        match interaction {
+            Interaction::Clicked => {
+                if let Some(item) = items.get(*index) {
+                    activated.send(MenuActivated(*item));
+                }
+            }
+            Interaction::Hovered if state.selected != *index => state.selected = *index,
+            _ => {}
+        }
+    }
+}
+
+/// Acts on picked entries, starting a level with [`LoadLevel`] once the player has chosen one.
+fn menu_activation(
+    mut activated: EventReader<MenuActivated>,
+    mut state: ResMut<MenuState>,
+    mut progress: ResMut<LevelPackProgress>,
+    assets: LevelAssets,
+    mut bindings: ResMut<ActionBindings>,
+    mut load_level: EventWriter<LoadLevel>,
+    mut exit: EventWriter<AppExit>,
+) {
+    let LevelAssets { packs, levels, .. } = assets;
+    for MenuActivated(item) in activated.iter() {
+        if !is_enabled(*item, &progress, &packs, &levels) {
+            continue;
+        }
+        match *item {
+            MenuItem::Continue => {
+                let level = progress.level;
+                if let Some(level) = progress.start(level, &packs, &levels) {
+                    load_level.send(level);
+                }
+            }
+            MenuItem::NewGame => state.open(MenuPage::NewGame),
+            MenuItem::LevelSelect => state.open(MenuPage::LevelSelect),
+            MenuItem::Settings => state.open(MenuPage::Settings),
+            MenuItem::Quit => exit.send(AppExit),
+            MenuItem::Daily => {
+                progress.leave();
+                load_level.send(daily_level());
+            }
+            MenuItem::Dimensions | MenuItem::Length => state.cycle(*item),
+            // Moves on from the seed, as keyboard moves are typed into it instead.
+            MenuItem::Seed => state.selected += 1,
+            MenuItem::LastSeed => {
+                if let Some((seed, version)) = progress.last_seed {
+                    state.seed = seed.to_string();
+                    state.version = version;
+                }
+            }
+            MenuItem::Start => {
+                progress.leave();
+                load_level.send(state.custom_level());
+            }
+            MenuItem::Level(index) => {
+                if let Some(level) = progress.start(index, &packs, &levels) {
+                    load_level.send(level);
+                }
+            }
+            MenuItem::ResetControls => *bindings = ActionBindings::default(),
+            MenuItem::Back if state.page != MenuPage::Main => state.open(MenuPage::Main),
+            _ => {}
+        }
+    }
+}
+
+/// Acts on the first entry picked from the pause menu.
+fn pause_activation(
+    mut activated: EventReader<MenuActivated>,
+    info: Res<LevelInfo>,
+    mut progress: ResMut<LevelPackProgress>,
+    mut app_state: ResMut<State<AppState>>,
+    mut load_level: EventWriter<LoadLevel>,
+) {
+    let item = match activated.iter().next() {
+        Some(MenuActivated(item)) => *item,
+        None => return,
+    };
+    match item {
+        // Popping resumes the level where it was, without spawning it again.
+        MenuItem::Resume | MenuItem::Back => {
+            if let Err(e) = app_state.pop() {
+                warn!("Couldn't resume the level: {}", e);
+            }
+        }
+        MenuItem::Restart => load_level.send(info.source.clone()),
+        MenuItem::Reseed => {
+            progress.leave();
+            load_level.send(info.source.reseeded());
+        }
+        MenuItem::QuitToMenu => {
+            progress.leave();
+            if let Err(e) = app_state.replace(AppState::MainMenu) {
+                warn!("Couldn't return to the main menu: {}", e);
+            }
+        }
+        _ => {}
+    }
+}
+
+fn open_main_page(mut state: ResMut<MenuState>) {
+    state.open(MenuPage::Main);
+}
+
+fn open_pause_page(mut state: ResMut<MenuState>) {
+    state.open(MenuPage::Pause);
+}
+
+/// Rebuilds the menu once the pack levels arrive, so their titles show.
+fn refresh_menu_on_load(
+    mut pack_events: EventReader<AssetEvent<LevelPack>>,
+    mut level_events: EventReader<AssetEvent<MazeLevelAsset>>,
+    mut state: ResMut<MenuState>,
+) {
+    if pack_events.iter().count() + level_events.iter().count() > 0 {
+        state.set_changed();
+    }
+}
+
+/// Spawns the menu, and rebuilds it whenever what it shows changes.
+fn render_menu(
+    mut c: Commands,
+    state: Res<MenuState>,
+    progress: Res<LevelPackProgress>,
+    rotation: Res<AxisRotation>,
+    assets: LevelAssets,
+    roots: Query<Entity, With<MenuRoot>>,
+    asset_server: Res<AssetServer>,
+) {
+    let unchanged = !state.is_changed() && !progress.is_changed() && !rotation.is_changed();
+    if unchanged && !roots.is_empty() {
+        return;
+    }
+    for root in roots.iter() {
+        c.entity(root).despawn_recursive();
+    }
+
+    let LevelAssets { packs, levels, .. } = assets;
+    let style = TextStyle {
+        font: asset_server.load("fonts\\UnicaOne-Regular.ttf"),
+        font_size: 50.0,
+        color: Color::WHITE,
+    };
+
+    let label = |s: String, font_size: f32, color: Color| TextBundle {
+        text: Text::with_section(
+            s,
+            TextStyle {
+                font_size,
+                color,
+                ..style.clone()
+            },
+            TextAlignment {
+                vertical: VerticalAlign::Center,
+                horizontal: HorizontalAlign::Center,
+            },
+        ),
+        ..default()
+    };
+
+    let items = state.items(progress.level_count(&packs));
+    c.spawn_bundle(NodeBundle {
+        style: Style {
+            size: Size::new(Val::Percent(100.0), Val::Percent(100.0)),
+            flex_direction: FlexDirection::ColumnReverse,
+            justify_content: JustifyContent::Center,
+            align_items: AlignItems::Center,
+            ..default()
+        },
+        color: if state.page == MenuPage::Pause {
+            Color::rgba(0.0, 0.0, 0.0, 0.6).into()
+        } else {
+            Color::NONE.into()
+        },
+        ..default()
+    })
+    .insert(MenuRoot)
+    .with_children(|c| {
+        let title = if state.page == MenuPage::Pause {
+            "Paused"
+        } else {
+            "Nothing Moves"
+        };
+        c.spawn_bundle(label(title.into(), 80.0, Color::WHITE));
+        for (index, item) in items.into_iter().enumerate() {
+            let color = if !is_enabled(item, &progress, &packs, &levels) {
+                Color::DARK_GRAY
+            } else if index == state.selected {
+                Color::WHITE
+            } else {
+                Color::GRAY
+            };
+            let text = item_label(item, &state, &progress, &packs, &levels, rotation.duration);
+            c.spawn_bundle(ButtonBundle {
+                style: Style {
+                    padding: Rect::all(Val::Px(4.0)),
+                    ..default()
+                },
+                color: Color::NONE.into(),
+                ..default()
+            })
+            .insert(MenuButton(index))
+            .with_children(|c| {
+                c.spawn_bundle(label(text, 50.0, color));
+            });
+        }
+    });
+}
+
+fn despawn_menu(mut c: Commands, roots: Query<Entity, With<MenuRoot>>) {
+    for root in roots.iter() {
+        c.entity(root).despawn_recursive();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The seed source and version of a generated level.
+    fn generated(level: LoadLevel) -> (RngSource, GeneratorVersion) {
+        match level.layout {
+            LayoutSource::Generated {
+                rng_source,
+                version,
+                ..
+            } => (rng_source, version),
+            LayoutSource::Stored(_) => panic!("the level wasn't generated"),
+        }
+    }
+
+    #[test]
+    fn pickers_stay_in_range() {
+        let mut state = MenuState::default();
+        for _ in 0..10 {
+            state.adjust(MenuItem::Dimensions, Direction::Positive);
+            state.adjust(MenuItem::Length, Direction::Negative);
+        }
+        assert_eq!(state.dimensions, *DIMENSIONS.end());
+        assert_eq!(state.length, *LENGTHS.start());
+        assert!(!state.adjust(MenuItem::Start, Direction::Positive));
+
+        state.cycle(MenuItem::Dimensions);
+        assert_eq!(state.dimensions, *DIMENSIONS.start());
+    }
+
+    #[test]
+    fn selection_wraps_around() {
+        let mut state = MenuState::default();
+        state.step_selection(Direction::Positive, 5);
+        assert_eq!(state.selected, 4);
+        state.step_selection(Direction::Negative, 5);
+        assert_eq!(state.selected, 0);
+    }
+
+    #[test]
+    fn seed_takes_typed_text() {
+        let mut state = MenuState::default();
+        for c in "4a2\u{8}\r7".chars() {
+            state.type_seed(c);
+        }
+        assert_eq!(state.seed, "4a7");
+        assert!(matches!(
+            generated(state.custom_level()),
+            (RngSource::Phrase(phrase), _) if phrase == "4a7"
+        ));
+
+        for _ in 0..40 {
+            state.type_seed('9');
+        }
+        assert_eq!(state.seed.len(), MAX_SEED_LENGTH);
+    }
+
+    #[test]
+    fn reused_seeds_keep_their_version() {
+        let mut state = MenuState {
+            seed: "42".into(),
+            version: GeneratorVersion::V1,
+            ..default()
+        };
+        assert!(matches!(
+            generated(state.custom_level()),
+            (RngSource::Seeded(42), GeneratorVersion::V1)
+        ));
+
+        state.type_seed('7');
+        assert_eq!(generated(state.custom_level()).1, GeneratorVersion::LATEST);
+    }
+
+    #[test]
+    fn custom_level_uses_choices() {
+        let mut state = MenuState::default();
+        assert!(matches!(
+            generated(state.custom_level()).0,
+            RngSource::Entropy
+        ));
+        state.type_seed('7');
+        let level = state.custom_level();
+        assert!(matches!(generated(level.clone()).0, RngSource::Seeded(7)));
+        assert!(matches!(
+            level.dimensions,
+            DimensionLength::Three([4, 4, 4])
+        ));
+        assert_eq!(state.items(2).len(), 6);
+
+        state.open(MenuPage::LevelSelect);
+        assert_eq!(
+            state.items(2),
+            [MenuItem::Level(0), MenuItem::Level(1), MenuItem::Back]
+        );
+    }
+}